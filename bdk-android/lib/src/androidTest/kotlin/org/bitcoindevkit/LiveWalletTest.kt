@@ -5,6 +5,7 @@ import androidx.test.ext.junit.runners.AndroidJUnit4
 import androidx.test.platform.app.InstrumentationRegistry
 import org.junit.runner.RunWith
 import kotlin.test.AfterTest
+import kotlin.test.assertNull
 import kotlin.test.assertTrue
 import java.io.File
 import org.rustbitcoin.bitcoin.Network
@@ -52,6 +53,10 @@ class LiveWalletTest {
             println("Transaction: ${tx.transaction.computeTxid()}")
             println("Sent ${sentAndReceived.sent}")
             println("Received ${sentAndReceived.received}")
+            val chainPosition = tx.chainPosition
+            if (chainPosition is ChainPosition.Unconfirmed) {
+                assertNull(chainPosition.replacedBy, "Transactions in the canonical history are never replaced")
+            }
         }
     }
 
@@ -0,0 +1,521 @@
+use crate::types::{AddressBalance, Balance as FfiBalance, BalanceGranularity, BalancePoint};
+
+use bdk_wallet::bitcoin::{Amount, OutPoint, Txid};
+use bdk_wallet::chain::{ChainPosition, ConfirmationTime};
+use bdk_wallet::{Balance, KeychainKind, LocalOutput, Wallet};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+// Number of confirmations before a coinbase output can be spent.
+const COINBASE_MATURITY: u32 = 100;
+
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Balance of the outputs owned by the given derivation indices, classified the same way
+/// `Wallet::balance` classifies the whole wallet.
+fn balance_of(
+    wallet: &Wallet,
+    outpoints: impl IntoIterator<Item = ((KeychainKind, u32), OutPoint)>,
+) -> Balance {
+    wallet.tx_graph().balance(
+        wallet.local_chain(),
+        wallet.local_chain().tip().block_id(),
+        outpoints,
+        |&(keychain, _), _| keychain == KeychainKind::Internal,
+    )
+}
+
+pub(crate) fn keychain_balance(wallet: &Wallet, keychain: KeychainKind) -> Balance {
+    let outpoints = wallet
+        .spk_index()
+        .outpoints()
+        .iter()
+        .filter(|((output_keychain, _), _)| *output_keychain == keychain)
+        .cloned();
+    balance_of(wallet, outpoints)
+}
+
+/// Outputs of one derivation index, gathered in a single pass over the wallet's outputs.
+#[derive(Default)]
+struct IndexOutputs {
+    outpoints: Vec<OutPoint>,
+    received: Amount,
+    frozen: Amount,
+}
+
+/// Balance, total amount ever received and frozen amount for every revealed index of `keychain`,
+/// including indices that never received anything.
+pub(crate) fn address_balances(
+    wallet: &Wallet,
+    keychain: KeychainKind,
+    frozen_outpoints: &HashSet<OutPoint>,
+) -> Vec<AddressBalance> {
+    let last_index = match wallet.derivation_index(keychain) {
+        Some(index) => index,
+        None => return Vec::new(),
+    };
+
+    let mut by_index: BTreeMap<u32, IndexOutputs> = BTreeMap::new();
+    for output in wallet
+        .list_output()
+        .filter(|output| output.keychain == keychain)
+    {
+        let outputs = by_index.entry(output.derivation_index).or_default();
+        outputs.outpoints.push(output.outpoint);
+        outputs.received += output.txout.value;
+        if !output.is_spent && frozen_outpoints.contains(&output.outpoint) {
+            outputs.frozen += output.txout.value;
+        }
+    }
+
+    (0..=last_index)
+        .map(|index| {
+            let outputs = by_index.remove(&index).unwrap_or_default();
+            let outpoints = outputs
+                .outpoints
+                .into_iter()
+                .map(|outpoint| ((keychain, index), outpoint));
+            AddressBalance {
+                keychain,
+                index,
+                address: Arc::new(wallet.peek_address(keychain, index).address.into()),
+                total_received: Arc::new(outputs.received.into()),
+                balance: FfiBalance::new(balance_of(wallet, outpoints), outputs.frozen),
+            }
+        })
+        .collect()
+}
+
+/// `Wallet::balance`, except that outputs with fewer than `min_confirmations` confirmations count
+/// as pending rather than confirmed.
+pub(crate) fn balance_with_min_confirmations(wallet: &Wallet, min_confirmations: u32) -> Balance {
+    let tip_height = wallet.latest_checkpoint().height();
+
+    let mut deep = Vec::new();
+    let mut shallow = Balance::default();
+    for output in wallet.list_unspent() {
+        let is_shallow = match output.confirmation_time {
+            ConfirmationTime::Confirmed { height, .. } => {
+                let confirmations = tip_height.saturating_sub(height) + 1;
+                // Immature coinbase outputs stay immature rather than pending.
+                let is_immature_coinbase = confirmations < COINBASE_MATURITY
+                    && wallet
+                        .tx_graph()
+                        .get_tx(output.outpoint.txid)
+                        .map_or(false, |tx| tx.is_coinbase());
+                confirmations < min_confirmations && !is_immature_coinbase
+            }
+            ConfirmationTime::Unconfirmed { .. } => false,
+        };
+
+        if is_shallow {
+            match output.keychain {
+                KeychainKind::Internal => shallow.trusted_pending += output.txout.value,
+                KeychainKind::External => shallow.untrusted_pending += output.txout.value,
+            }
+        } else {
+            deep.push(((output.keychain, output.derivation_index), output.outpoint));
+        }
+    }
+    balance_of(wallet, deep) + shallow
+}
+
+/// Total value of the unspent outputs in `outpoints` that also satisfy `include`.
+pub(crate) fn unspent_value(
+    wallet: &Wallet,
+    outpoints: &HashSet<OutPoint>,
+    include: impl Fn(&LocalOutput) -> bool,
+) -> Amount {
+    wallet
+        .list_unspent()
+        .filter(|output| outpoints.contains(&output.outpoint) && include(output))
+        .map(|output| output.txout.value)
+        .sum()
+}
+
+/// Height and time of the block every canonical transaction was mined in, when known.
+///
+/// bdk_chain leaves an unanchored parent of a confirmed transaction unconfirmed, although it was
+/// mined no later than its child. It is given the block of its earliest confirmed child, so that
+/// confirmed history never spends coins it has not received yet.
+pub(crate) fn confirmations(wallet: &Wallet) -> HashMap<Txid, (u32, u64)> {
+    let mut confirmations: HashMap<Txid, (u32, u64)> = HashMap::new();
+    let mut txs = HashMap::new();
+    for tx in wallet.transactions() {
+        if let ChainPosition::Confirmed(anchor) = tx.chain_position {
+            confirmations.insert(
+                tx.tx_node.txid,
+                (anchor.block_id.height, anchor.confirmation_time),
+            );
+        }
+        txs.insert(tx.tx_node.txid, tx.tx_node.tx);
+    }
+
+    let mut anchored: Vec<(Txid, (u32, u64))> = confirmations
+        .iter()
+        .map(|(txid, confirmation)| (*txid, *confirmation))
+        .collect();
+    anchored.sort_unstable_by_key(|(_, confirmation)| *confirmation);
+    for (txid, confirmation) in anchored {
+        let mut stack = vec![txid];
+        while let Some(tx) = stack.pop().and_then(|txid| txs.get(&txid)) {
+            for input in &tx.input {
+                let parent = input.previous_output.txid;
+                if txs.contains_key(&parent) && !confirmations.contains_key(&parent) {
+                    confirmations.insert(parent, confirmation);
+                    stack.push(parent);
+                }
+            }
+        }
+    }
+    confirmations
+}
+
+/// Running confirmed balance after each bucket of confirmed transactions, oldest first. Only
+/// canonical transactions count, so replaced transactions never show up in the history. If the
+/// wallet has unconfirmed transactions, a last point at the tip carries their combined delta.
+pub(crate) fn balance_history(
+    wallet: &Wallet,
+    granularity: BalanceGranularity,
+) -> Vec<BalancePoint> {
+    let confirmations = confirmations(wallet);
+    let mut confirmed = Vec::new();
+    let mut unconfirmed_delta: i64 = 0;
+    let mut last_seen: Option<u64> = None;
+    for tx in wallet.transactions() {
+        let (sent, received) = wallet.sent_and_received(&tx.tx_node.tx);
+        let delta = received.to_sat() as i64 - sent.to_sat() as i64;
+        match (confirmations.get(&tx.tx_node.txid), tx.chain_position) {
+            (Some((height, time)), _) => confirmed.push((*height, *time, delta)),
+            (None, ChainPosition::Confirmed(anchor)) => {
+                confirmed.push((anchor.block_id.height, anchor.confirmation_time, delta))
+            }
+            (None, ChainPosition::Unconfirmed(seen_at)) => {
+                unconfirmed_delta += delta;
+                last_seen = Some(last_seen.map_or(seen_at, |last_seen| last_seen.max(seen_at)));
+            }
+        }
+    }
+    confirmed.sort_unstable();
+
+    // Transactions in a block are not in spending order, so the balance is only checked once
+    // every transaction of a bucket has been added.
+    let mut buckets: Vec<(u64, BalancePoint, i64)> = Vec::new();
+    let mut running: i64 = 0;
+    // Block timestamps may decrease by up to an hour, clamp them so the history never goes back.
+    let mut latest_time: u64 = 0;
+    for (height, time, delta) in confirmed {
+        running += delta;
+        latest_time = latest_time.max(time);
+        let bucket = match granularity {
+            BalanceGranularity::Block => u64::from(height),
+            _ => bucket_start(granularity, latest_time),
+        };
+        let point = BalancePoint {
+            timestamp: match granularity {
+                BalanceGranularity::Block => latest_time,
+                _ => bucket,
+            },
+            height: Some(height),
+            confirmed: Arc::new(Amount::ZERO.into()),
+            unconfirmed_delta: 0,
+        };
+        // Later transactions in the same bucket replace its point with the newer balance.
+        if buckets
+            .last()
+            .map_or(false, |(last_bucket, _, _)| *last_bucket == bucket)
+        {
+            buckets.pop();
+        }
+        buckets.push((bucket, point, running));
+    }
+
+    let mut points: Vec<BalancePoint> = buckets
+        .into_iter()
+        .map(|(_, point, running)| BalancePoint {
+            confirmed: Arc::new(confirmed_amount(running).into()),
+            ..point
+        })
+        .collect();
+    if let Some(last_seen) = last_seen {
+        points.push(BalancePoint {
+            timestamp: last_seen,
+            height: None,
+            confirmed: Arc::new(confirmed_amount(running).into()),
+            unconfirmed_delta,
+        });
+    }
+    points
+}
+
+/// The running balance only goes negative when confirmed transactions spend wallet outputs of
+/// transactions the wallet does not hold, which is reported as an empty balance.
+fn confirmed_amount(running: i64) -> Amount {
+    Amount::from_sat(u64::try_from(running).unwrap_or(0))
+}
+
+/// Start of the UTC day, ISO week (starting Monday) or calendar month containing `timestamp`.
+fn bucket_start(granularity: BalanceGranularity, timestamp: u64) -> u64 {
+    let days = timestamp / SECONDS_PER_DAY;
+    let start_day = match granularity {
+        BalanceGranularity::Block | BalanceGranularity::Day => days,
+        // 1970-01-01 was a Thursday, so its week is clamped to start at the epoch.
+        BalanceGranularity::Week => days.saturating_sub((days + 3) % 7),
+        BalanceGranularity::Month => {
+            let (_, _, day) = civil_from_days(days);
+            days + 1 - day
+        }
+    };
+    start_day * SECONDS_PER_DAY
+}
+
+/// Year, month and day of the month for a count of days since 1970-01-01, after Howard Hinnant's
+/// `civil_from_days`.
+pub(crate) fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    // Months are counted from March, so January and February belong to the next year.
+    let (month, year_offset) = if month_index < 10 {
+        (month_index + 3, 0)
+    } else {
+        (month_index - 9, 1)
+    };
+    (era * 400 + year_of_era + year_offset, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::balances::{
+        address_balances, balance_history, balance_with_min_confirmations, bucket_start,
+    };
+    use crate::test_utils::{confirmed, foreign_outpoint, new_tx, new_wallet, unconfirmed};
+    use crate::types::BalanceGranularity;
+
+    use bdk_wallet::bitcoin::{Amount, OutPoint, ScriptBuf, TxOut};
+    use bdk_wallet::KeychainKind;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_balance_history_buckets() {
+        // 2024-02-29T13:45:00Z, a Thursday in a leap year.
+        let timestamp = 1_709_214_300;
+        assert_eq!(
+            bucket_start(BalanceGranularity::Day, timestamp),
+            1_709_164_800
+        );
+        // Monday 2024-02-26.
+        assert_eq!(
+            bucket_start(BalanceGranularity::Week, timestamp),
+            1_708_905_600
+        );
+        // 2024-02-01.
+        assert_eq!(
+            bucket_start(BalanceGranularity::Month, timestamp),
+            1_706_745_600
+        );
+        // 1970-01-01 itself.
+        assert_eq!(bucket_start(BalanceGranularity::Month, 0), 0);
+        assert_eq!(bucket_start(BalanceGranularity::Week, 0), 0);
+    }
+
+    #[test]
+    fn test_balance_history() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let change = wallet
+            .reveal_next_address(KeychainKind::Internal)
+            .script_pubkey();
+
+        // Only the child is anchored, the parent it spends is still known as unconfirmed.
+        let parent = new_tx(&[foreign_outpoint(1)], vec![(address.clone(), 5_000)]);
+        let child = new_tx(
+            &[OutPoint::new(parent.compute_txid(), 0)],
+            vec![(ScriptBuf::new(), 4_000)],
+        );
+        wallet
+            .apply_update_at(unconfirmed(&[&parent], 500), None)
+            .unwrap();
+        let update = confirmed(&wallet, &[&child], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+
+        let funding = new_tx(&[foreign_outpoint(2)], vec![(address.clone(), 50_000)]);
+        let update = confirmed(&wallet, &[&funding], 2, 86_500);
+        wallet.apply_update(update).unwrap();
+        // Mined earlier than its predecessor by the clock, in the previous day.
+        let deposit = new_tx(&[foreign_outpoint(3)], vec![(address.clone(), 10_000)]);
+        let update = confirmed(&wallet, &[&deposit], 3, 86_000);
+        wallet.apply_update(update).unwrap();
+        let payment = new_tx(
+            &[OutPoint::new(funding.compute_txid(), 0)],
+            vec![(ScriptBuf::new(), 30_000), (change, 19_000)],
+        );
+        let update = confirmed(&wallet, &[&payment], 4, 180_000);
+        wallet.apply_update(update).unwrap();
+        let incoming = new_tx(&[foreign_outpoint(4)], vec![(address, 7_000)]);
+        wallet
+            .apply_update_at(unconfirmed(&[&incoming], 190_000), None)
+            .unwrap();
+
+        let points = |granularity| -> Vec<(u64, Option<u32>, u64, i64)> {
+            balance_history(&wallet, granularity)
+                .into_iter()
+                .map(|point| {
+                    (
+                        point.timestamp,
+                        point.height,
+                        point.confirmed.0.to_sat(),
+                        point.unconfirmed_delta,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(
+            points(BalanceGranularity::Block),
+            vec![
+                (1_000, Some(1), 0, 0),
+                (86_500, Some(2), 50_000, 0),
+                (86_500, Some(3), 60_000, 0),
+                (180_000, Some(4), 29_000, 0),
+                (190_000, None, 29_000, 7_000),
+            ]
+        );
+        assert_eq!(
+            points(BalanceGranularity::Day),
+            vec![
+                (0, Some(1), 0, 0),
+                (86_400, Some(3), 60_000, 0),
+                (172_800, Some(4), 29_000, 0),
+                (190_000, None, 29_000, 7_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_balance_history_missing_parent() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+
+        // The wallet only knows the output the confirmed spend consumes, not its transaction.
+        let mut update = confirmed(
+            &wallet,
+            &[&new_tx(
+                &[foreign_outpoint(1)],
+                vec![(ScriptBuf::new(), 4_000)],
+            )],
+            1,
+            1_000,
+        );
+        update.tx_update.txouts.insert(
+            foreign_outpoint(1),
+            TxOut {
+                value: Amount::from_sat(5_000),
+                script_pubkey: address,
+            },
+        );
+        wallet.apply_update(update).unwrap();
+
+        let points = balance_history(&wallet, BalanceGranularity::Block);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].height, Some(1));
+        assert_eq!(points[0].confirmed.0, Amount::ZERO);
+    }
+
+    #[test]
+    fn test_address_balances() {
+        let mut wallet = new_wallet();
+        let address_0 = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let address_1 = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        wallet.reveal_next_address(KeychainKind::External);
+        let change = wallet
+            .reveal_next_address(KeychainKind::Internal)
+            .script_pubkey();
+
+        let funding = new_tx(
+            &[foreign_outpoint(1)],
+            vec![(address_0, 50_000), (address_1, 20_000)],
+        );
+        let update = confirmed(&wallet, &[&funding], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+        let spend = new_tx(
+            &[OutPoint::new(funding.compute_txid(), 0)],
+            vec![(change, 30_000), (ScriptBuf::new(), 19_000)],
+        );
+        wallet.apply_update(unconfirmed(&[&spend], 2_000)).unwrap();
+
+        let frozen = HashSet::from([OutPoint::new(funding.compute_txid(), 1)]);
+        let external = address_balances(&wallet, KeychainKind::External, &frozen);
+        assert_eq!(external.len(), 3);
+
+        // Everything received on the first address has been spent.
+        assert_eq!(external[0].total_received.0, Amount::from_sat(50_000));
+        assert_eq!(external[0].balance.total.0, Amount::ZERO);
+
+        assert_eq!(external[1].total_received.0, Amount::from_sat(20_000));
+        assert_eq!(external[1].balance.confirmed.0, Amount::from_sat(20_000));
+        assert_eq!(external[1].balance.frozen.0, Amount::from_sat(20_000));
+
+        assert_eq!(external[2].index, 2);
+        assert_eq!(external[2].total_received.0, Amount::ZERO);
+        assert_eq!(external[2].balance.total.0, Amount::ZERO);
+
+        let internal = address_balances(&wallet, KeychainKind::Internal, &frozen);
+        assert_eq!(internal.len(), 1);
+        assert_eq!(
+            internal[0].balance.trusted_pending.0,
+            Amount::from_sat(30_000)
+        );
+        assert_eq!(internal[0].balance.frozen.0, Amount::ZERO);
+    }
+
+    #[test]
+    fn test_balance_with_min_confirmations() {
+        let mut wallet = new_wallet();
+        let address_0 = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let address_1 = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let address_2 = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+
+        let deep = new_tx(&[foreign_outpoint(1)], vec![(address_0, 10_000)]);
+        let update = confirmed(&wallet, &[&deep], 2, 1_000);
+        wallet.apply_update(update).unwrap();
+        let shallow = new_tx(&[foreign_outpoint(2)], vec![(address_1, 20_000)]);
+        let coinbase = new_tx(&[OutPoint::null()], vec![(address_2, 50_000)]);
+        let update = confirmed(&wallet, &[&shallow, &coinbase], 10, 2_000);
+        wallet.apply_update(update).unwrap();
+
+        let balance = balance_with_min_confirmations(&wallet, 1);
+        assert_eq!(balance, wallet.balance());
+        assert_eq!(balance.confirmed, Amount::from_sat(30_000));
+        assert_eq!(balance.immature, Amount::from_sat(50_000));
+
+        // Nine confirmations for the deep output, one for the shallow output and the coinbase.
+        let balance = balance_with_min_confirmations(&wallet, 3);
+        assert_eq!(balance.confirmed, Amount::from_sat(10_000));
+        assert_eq!(balance.untrusted_pending, Amount::from_sat(20_000));
+        assert_eq!(balance.trusted_pending, Amount::ZERO);
+        assert_eq!(balance.immature, Amount::from_sat(50_000));
+        assert_eq!(balance.total(), wallet.balance().total());
+    }
+}
@@ -1,29 +1,66 @@
 use crate::error::{
-    AddressParseError, FromScriptError, PsbtError, PsbtParseError, TransactionError,
+    AddressParseError, BlockError, FromScriptError, PsbtEditError, PsbtError, PsbtJoinError,
+    PsbtParseError, PublicKeyError, QrError, SighashError, TransactionError,
+    TransactionVerifyError,
+};
+use crate::keys::DerivationPath;
+use crate::qr::{encode_frames, QrEncoding, QrPayload};
+use crate::script::ScriptType;
+use crate::types::{
+    AddressType, DecodedTransaction, DecodedTxIn, DecodedTxOut, ExtractTxWarning, InputSighash,
+    PolicyViolation, ProprietaryKeyValue, PsbtMap, SequenceInfo, SighashKind, UncheckedExtractedTx,
+    UnknownKeyValue,
 };
 
+use bitcoin_ffi::FeeRate;
 use bitcoin_ffi::OutPoint;
 use bitcoin_ffi::Script;
 
-use bdk_bitcoind_rpc::bitcoincore_rpc::jsonrpc::serde_json;
+use bdk_wallet::bitcoin::absolute::LockTime;
+use bdk_wallet::bitcoin::address::AddressType as BdkAddressType;
 use bdk_wallet::bitcoin::address::{NetworkChecked, NetworkUnchecked};
-use bdk_wallet::bitcoin::consensus::encode::serialize;
+use bdk_wallet::bitcoin::bip32::{Fingerprint, KeySource, Xpub};
+use bdk_wallet::bitcoin::block::Header as BdkHeader;
+use bdk_wallet::bitcoin::consensus::encode::{deserialize, serialize};
 use bdk_wallet::bitcoin::consensus::Decodable;
+use bdk_wallet::bitcoin::hashes::{sha256d, Hash};
+use bdk_wallet::bitcoin::hex::DisplayHex;
 use bdk_wallet::bitcoin::io::Cursor;
+use bdk_wallet::bitcoin::key::{CompressedPublicKey, TweakedPublicKey};
+use bdk_wallet::bitcoin::psbt::raw::{Key as RawKey, ProprietaryKey};
+use bdk_wallet::bitcoin::psbt::Error as BdkPsbtError;
 use bdk_wallet::bitcoin::psbt::ExtractTxError;
+use bdk_wallet::bitcoin::script::Instruction;
+use bdk_wallet::bitcoin::secp256k1::rand::seq::SliceRandom;
+use bdk_wallet::bitcoin::secp256k1::rand::thread_rng;
+use bdk_wallet::bitcoin::secp256k1::{
+    Message, PublicKey as Secp256k1PublicKey, Secp256k1, XOnlyPublicKey,
+};
+use bdk_wallet::bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bdk_wallet::bitcoin::transaction::Version;
 use bdk_wallet::bitcoin::Address as BdkAddress;
+use bdk_wallet::bitcoin::Amount as BdkAmount;
+use bdk_wallet::bitcoin::Block as BdkBlock;
+use bdk_wallet::bitcoin::FeeRate as BdkFeeRate;
+use bdk_wallet::bitcoin::MerkleBlock as BdkMerkleBlock;
 use bdk_wallet::bitcoin::Network;
+use bdk_wallet::bitcoin::OutPoint as BdkOutPoint;
 use bdk_wallet::bitcoin::Psbt as BdkPsbt;
 use bdk_wallet::bitcoin::Transaction as BdkTransaction;
 use bdk_wallet::bitcoin::TxIn as BdkTxIn;
 use bdk_wallet::bitcoin::TxOut as BdkTxOut;
+use bdk_wallet::bitcoin::{ecdsa, taproot, PublicKey, Script as BdkScript, ScriptBuf, TapLeafHash};
+use bdk_wallet::bitcoin::{BlockHash, CompactTarget, Sequence, TapNodeHash, Target, TxMerkleNode};
+use bdk_wallet::bitcoin::{Txid, Witness, Wtxid};
 
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt::Display;
 use std::ops::Deref;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Address(BdkAddress<NetworkChecked>);
 
 impl Address {
@@ -40,6 +77,57 @@ impl Address {
         Ok(Address(address))
     }
 
+    pub fn p2pkh(public_key: Vec<u8>, network: Network) -> Result<Self, PublicKeyError> {
+        let public_key = parse_public_key(&public_key)?;
+        Ok(Address(BdkAddress::p2pkh(
+            public_key.pubkey_hash(),
+            network,
+        )))
+    }
+
+    pub fn p2wpkh(public_key: Vec<u8>, network: Network) -> Result<Self, PublicKeyError> {
+        let public_key = parse_compressed_public_key(&public_key)?;
+        Ok(Address(BdkAddress::p2wpkh(&public_key, network)))
+    }
+
+    pub fn p2shwpkh(public_key: Vec<u8>, network: Network) -> Result<Self, PublicKeyError> {
+        let public_key = parse_compressed_public_key(&public_key)?;
+        Ok(Address(BdkAddress::p2shwpkh(&public_key, network)))
+    }
+
+    pub fn p2wsh(witness_script: Arc<Script>, network: Network) -> Self {
+        Address(BdkAddress::p2wsh(&witness_script.0, network))
+    }
+
+    pub fn p2tr(
+        internal_key: Vec<u8>,
+        merkle_root: Option<Vec<u8>>,
+        network: Network,
+    ) -> Result<Self, PublicKeyError> {
+        let internal_key = parse_x_only_public_key(&internal_key)?;
+        let merkle_root = merkle_root
+            .map(|merkle_root| {
+                TapNodeHash::from_slice(&merkle_root).map_err(|_| PublicKeyError::InvalidMerkleRoot)
+            })
+            .transpose()?;
+        let secp = Secp256k1::verification_only();
+        Ok(Address(BdkAddress::p2tr(
+            &secp,
+            internal_key,
+            merkle_root,
+            network,
+        )))
+    }
+
+    /// Builds a taproot address from an output key that has already been tweaked.
+    pub fn p2tr_tweaked(output_key: Vec<u8>, network: Network) -> Result<Self, PublicKeyError> {
+        let output_key = parse_x_only_public_key(&output_key)?;
+        Ok(Address(BdkAddress::p2tr_tweaked(
+            TweakedPublicKey::dangerous_assume_tweaked(output_key),
+            network,
+        )))
+    }
+
     pub fn script_pubkey(&self) -> Arc<Script> {
         Arc::new(Script(self.0.script_pubkey()))
     }
@@ -56,6 +144,64 @@ impl Address {
             false
         }
     }
+
+    pub fn address_type(&self) -> AddressType {
+        match self.0.address_type() {
+            Some(BdkAddressType::P2pkh) => AddressType::P2pkh,
+            Some(BdkAddressType::P2sh) => AddressType::P2sh,
+            Some(BdkAddressType::P2wpkh) => AddressType::P2wpkh,
+            Some(BdkAddressType::P2wsh) => AddressType::P2wsh,
+            Some(BdkAddressType::P2tr) => AddressType::P2tr,
+            // Every address that is not one of the types above is a segwit address.
+            _ => AddressType::UnknownWitness {
+                version: self.witness_version().unwrap_or_default(),
+            },
+        }
+    }
+
+    pub fn witness_version(&self) -> Option<u8> {
+        self.0
+            .script_pubkey()
+            .witness_version()
+            .map(|version| version.to_num())
+    }
+
+    pub fn witness_program(&self) -> Option<Vec<u8>> {
+        let script_pubkey = self.0.script_pubkey();
+        if script_pubkey.is_witness_program() {
+            // The witness program follows the version opcode and a single push opcode.
+            Some(script_pubkey.as_bytes()[2..].to_vec())
+        } else {
+            None
+        }
+    }
+
+    pub fn pubkey_hash(&self) -> Option<Vec<u8>> {
+        self.0
+            .pubkey_hash()
+            .map(|hash| hash.to_byte_array().to_vec())
+    }
+
+    pub fn script_hash(&self) -> Option<Vec<u8>> {
+        self.0
+            .script_hash()
+            .map(|hash| hash.to_byte_array().to_vec())
+    }
+
+    pub fn is_related_to_pubkey(&self, public_key: Vec<u8>) -> Result<bool, PublicKeyError> {
+        let public_key = parse_public_key(&public_key)?;
+        Ok(self.0.is_related_to_pubkey(&public_key))
+    }
+
+    /// Testnet and signet share address encodings, as do testnet and regtest for base58
+    /// addresses, so those addresses report `Testnet`.
+    pub fn network(&self) -> Network {
+        [Network::Bitcoin, Network::Testnet, Network::Regtest]
+            .iter()
+            .copied()
+            .find(|network| self.is_valid_for_network(*network))
+            .unwrap_or(Network::Testnet)
+    }
 }
 
 impl Display for Address {
@@ -76,6 +222,10 @@ impl From<BdkAddress> for Address {
     }
 }
 
+/// The txid of a transaction with its script sigs and witnesses removed. rust-bitcoin has no
+/// dedicated type for it.
+pub type Ntxid = sha256d::Hash;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Transaction(BdkTransaction);
 
@@ -86,8 +236,100 @@ impl Transaction {
         Ok(Transaction(tx))
     }
 
-    pub fn compute_txid(&self) -> String {
-        self.0.compute_txid().to_string()
+    pub fn from_parts(version: i32, lock_time: u32, input: Vec<TxIn>, output: Vec<TxOut>) -> Self {
+        Transaction(BdkTransaction {
+            version: Version(version),
+            lock_time: LockTime::from_consensus(lock_time),
+            input: input.iter().map(BdkTxIn::from).collect(),
+            output: output.iter().map(BdkTxOut::from).collect(),
+        })
+    }
+
+    pub fn with_version(&self, version: i32) -> Arc<Self> {
+        let mut tx = self.0.clone();
+        tx.version = Version(version);
+        Arc::new(Transaction(tx))
+    }
+
+    pub fn with_lock_time(&self, lock_time: u32) -> Arc<Self> {
+        let mut tx = self.0.clone();
+        tx.lock_time = LockTime::from_consensus(lock_time);
+        Arc::new(Transaction(tx))
+    }
+
+    pub fn with_inputs(&self, input: Vec<TxIn>) -> Arc<Self> {
+        let mut tx = self.0.clone();
+        tx.input = input.iter().map(BdkTxIn::from).collect();
+        Arc::new(Transaction(tx))
+    }
+
+    pub fn with_outputs(&self, output: Vec<TxOut>) -> Arc<Self> {
+        let mut tx = self.0.clone();
+        tx.output = output.iter().map(BdkTxOut::from).collect();
+        Arc::new(Transaction(tx))
+    }
+
+    pub fn add_input(&self, input: TxIn) -> Arc<Self> {
+        let mut tx = self.0.clone();
+        tx.input.push(BdkTxIn::from(&input));
+        Arc::new(Transaction(tx))
+    }
+
+    pub fn add_output(&self, output: TxOut) -> Arc<Self> {
+        let mut tx = self.0.clone();
+        tx.output.push(BdkTxOut::from(&output));
+        Arc::new(Transaction(tx))
+    }
+
+    pub fn compute_txid(&self) -> Txid {
+        self.0.compute_txid()
+    }
+
+    pub fn compute_wtxid(&self) -> Wtxid {
+        self.0.compute_wtxid()
+    }
+
+    pub fn compute_ntxid(&self) -> Ntxid {
+        self.0.compute_ntxid()
+    }
+
+    pub fn input_sequences(&self) -> Vec<SequenceInfo> {
+        self.0
+            .input
+            .iter()
+            .map(|tx_in| SequenceInfo::from(tx_in.sequence))
+            .collect()
+    }
+
+    pub fn policy_violations(&self) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let version = self.0.version.0;
+        if !(1..=MAX_STANDARD_TX_VERSION).contains(&version) {
+            violations.push(PolicyViolation::NonStandardVersion { version });
+        }
+        let weight = self.0.weight().to_wu();
+        if weight > MAX_STANDARD_TX_WEIGHT {
+            violations.push(PolicyViolation::TxTooLarge { weight });
+        }
+        let non_witness_size = self.0.base_size() as u64;
+        if non_witness_size < MIN_STANDARD_TX_NONWITNESS_SIZE {
+            violations.push(PolicyViolation::TxTooSmall {
+                size: non_witness_size,
+            });
+        }
+        for (index, tx_out) in self.0.output.iter().enumerate() {
+            if tx_out.value < tx_out.script_pubkey.minimal_non_dust() {
+                violations.push(PolicyViolation::DustOutput {
+                    index: index as u32,
+                    value: tx_out.value.to_sat(),
+                });
+            }
+        }
+        violations
+    }
+
+    pub fn is_standard(&self) -> bool {
+        self.policy_violations().is_empty()
     }
 
     pub fn weight(&self) -> u64 {
@@ -133,6 +375,163 @@ impl Transaction {
     pub fn lock_time(&self) -> u32 {
         self.0.lock_time.to_consensus_u32()
     }
+
+    pub fn to_qr_frames(&self, encoding: QrEncoding) -> Result<Vec<String>, QrError> {
+        encode_frames(&serialize(&self.0), QrPayload::Transaction, encoding)
+    }
+
+    pub fn decode(&self, network: Network) -> DecodedTransaction {
+        let is_coinbase = self.0.is_coinbase();
+        let input = self
+            .0
+            .input
+            .iter()
+            .map(|tx_in| DecodedTxIn {
+                previous_output: OutPoint {
+                    txid: tx_in.previous_output.txid,
+                    vout: tx_in.previous_output.vout,
+                },
+                is_coinbase,
+                script_sig_asm: tx_in.script_sig.to_asm_string(),
+                script_sig_hex: tx_in.script_sig.to_hex_string(),
+                witness: tx_in
+                    .witness
+                    .iter()
+                    .map(|element| element.to_lower_hex_string())
+                    .collect(),
+                sequence: tx_in.sequence.0,
+            })
+            .collect();
+        let output = self
+            .0
+            .output
+            .iter()
+            .enumerate()
+            .map(|(n, tx_out)| DecodedTxOut {
+                n: n as u32,
+                value: tx_out.value.to_sat(),
+                script_pubkey_asm: tx_out.script_pubkey.to_asm_string(),
+                script_pubkey_hex: tx_out.script_pubkey.to_hex_string(),
+                script_type: ScriptType::from(tx_out.script_pubkey.as_script())
+                    .core_name()
+                    .to_string(),
+                address: BdkAddress::from_script(&tx_out.script_pubkey, network)
+                    .ok()
+                    .map(|address| address.to_string()),
+            })
+            .collect();
+
+        DecodedTransaction {
+            txid: self.0.compute_txid(),
+            wtxid: self.0.compute_wtxid(),
+            version: self.0.version.0,
+            lock_time: self.0.lock_time.to_consensus_u32(),
+            total_size: self.0.total_size() as u64,
+            vsize: self.0.vsize() as u64,
+            weight: self.0.weight().to_wu(),
+            input,
+            output,
+        }
+    }
+
+    /// Render the transaction in the same JSON layout as Bitcoin Core's `decoderawtransaction`.
+    pub fn decode_json(&self, network: Network) -> String {
+        let decoded = self.decode(network);
+        let vin: Vec<serde_json::Value> = decoded
+            .input
+            .iter()
+            .map(|input| {
+                let mut vin = if input.is_coinbase {
+                    serde_json::json!({ "coinbase": input.script_sig_hex })
+                } else {
+                    serde_json::json!({
+                        "txid": input.previous_output.txid.to_string(),
+                        "vout": input.previous_output.vout,
+                        "scriptSig": {
+                            "asm": input.script_sig_asm,
+                            "hex": input.script_sig_hex,
+                        },
+                    })
+                };
+                if !input.witness.is_empty() {
+                    vin["txinwitness"] = serde_json::json!(input.witness);
+                }
+                vin["sequence"] = serde_json::json!(input.sequence);
+                vin
+            })
+            .collect();
+        let vout: Vec<serde_json::Value> = decoded
+            .output
+            .iter()
+            .map(|output| {
+                let mut script_pubkey = serde_json::json!({
+                    "asm": output.script_pubkey_asm,
+                    "hex": output.script_pubkey_hex,
+                });
+                if let Some(address) = &output.address {
+                    script_pubkey["address"] = serde_json::json!(address);
+                }
+                script_pubkey["type"] = serde_json::json!(output.script_type);
+                serde_json::json!({
+                    "value": BdkAmount::from_sat(output.value).to_btc(),
+                    "n": output.n,
+                    "scriptPubKey": script_pubkey,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "txid": decoded.txid.to_string(),
+            "hash": decoded.wtxid.to_string(),
+            "version": decoded.version,
+            "size": decoded.total_size,
+            "vsize": decoded.vsize,
+            "weight": decoded.weight,
+            "locktime": decoded.lock_time,
+            "vin": vin,
+            "vout": vout,
+        })
+        .to_string()
+    }
+
+    pub fn verify(&self, prevouts: Vec<TxOut>) -> Result<(), TransactionVerifyError> {
+        if prevouts.len() != self.0.input.len() {
+            return Err(TransactionVerifyError::PrevoutCountMismatch {
+                inputs: self.0.input.len() as u32,
+                prevouts: prevouts.len() as u32,
+            });
+        }
+
+        let tx_bytes = serialize(&self.0);
+        let scripts: Vec<Vec<u8>> = prevouts
+            .iter()
+            .map(|prevout| prevout.script_pubkey.0.to_bytes())
+            .collect();
+        // Taproot verification commits to every spent output, so libbitcoinconsensus needs all
+        // of them for each input.
+        let spent_outputs: Vec<bitcoinconsensus::Utxo> = scripts
+            .iter()
+            .zip(&prevouts)
+            .map(|(script, prevout)| bitcoinconsensus::Utxo {
+                script_pubkey: script.as_ptr(),
+                script_pubkey_len: script.len() as u32,
+                value: prevout.value as i64,
+            })
+            .collect();
+
+        for (index, prevout) in prevouts.iter().enumerate() {
+            // With the spent outputs given, every soft fork up to and including taproot applies.
+            bitcoinconsensus::verify(
+                &scripts[index],
+                prevout.value,
+                &tx_bytes,
+                Some(&spent_outputs),
+                index,
+            )
+            .map_err(|e| TransactionVerifyError::from_consensus(index as u32, e))?;
+        }
+        Ok(())
+    }
 }
 
 impl From<BdkTransaction> for Transaction {
@@ -172,6 +571,51 @@ impl Psbt {
         Ok(Arc::new(transaction))
     }
 
+    pub(crate) fn extract_tx_with_fee_rate_limit(
+        &self,
+        max_fee_rate: &FeeRate,
+    ) -> Result<Arc<Transaction>, ExtractTxError> {
+        let tx: BdkTransaction = self
+            .0
+            .lock()
+            .unwrap()
+            .clone()
+            .extract_tx_with_fee_rate_limit(max_fee_rate.0)?;
+        let transaction: Transaction = tx.into();
+        Ok(Arc::new(transaction))
+    }
+
+    pub(crate) fn extract_tx_unchecked_fee_rate(&self) -> UncheckedExtractedTx {
+        let psbt = self.0.lock().unwrap().clone();
+        let fee = psbt.fee();
+        let tx: BdkTransaction = psbt.extract_tx_unchecked_fee_rate();
+
+        // Run the checks that extract_tx would have failed on, but report them instead. Like
+        // extract_tx, the fee rate uses the weight of the extracted, signed transaction.
+        let warning = match fee {
+            Ok(fee) => {
+                let fee_rate = BdkFeeRate::from_sat_per_kwu(
+                    fee.to_sat().saturating_mul(1000) / tx.weight().to_wu(),
+                );
+                (fee_rate > BdkPsbt::DEFAULT_MAX_FEE_RATE).then(|| {
+                    ExtractTxWarning::AbsurdFeeRate {
+                        fee_rate: fee_rate.to_sat_per_vb_ceil(),
+                    }
+                })
+            }
+            Err(BdkPsbtError::NegativeFee) => Some(ExtractTxWarning::SendingTooMuch),
+            Err(BdkPsbtError::FeeOverflow) => Some(ExtractTxWarning::AbsurdFeeRate {
+                fee_rate: BdkFeeRate::MAX.to_sat_per_vb_ceil(),
+            }),
+            Err(_) => Some(ExtractTxWarning::MissingInputValue),
+        };
+
+        UncheckedExtractedTx {
+            transaction: Arc::new(tx.into()),
+            warning,
+        }
+    }
+
     pub(crate) fn fee(&self) -> Result<u64, PsbtError> {
         self.0
             .lock()
@@ -188,10 +632,392 @@ impl Psbt {
         Ok(Arc::new(Psbt(Mutex::new(original_psbt))))
     }
 
+    pub(crate) fn join(
+        &self,
+        others: Vec<Arc<Psbt>>,
+        shuffle: bool,
+    ) -> Result<Arc<Psbt>, PsbtJoinError> {
+        let mut joined = self.0.lock().unwrap().clone();
+        ensure_unsigned(&joined)?;
+        let mut outpoints: HashSet<_> = joined
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|input| input.previous_output)
+            .collect();
+
+        for other in others {
+            let other = other.0.lock().unwrap().clone();
+            ensure_unsigned(&other)?;
+            if other.version != joined.version {
+                return Err(PsbtJoinError::PsbtVersionMismatch);
+            }
+            if other.unsigned_tx.version != joined.unsigned_tx.version {
+                return Err(PsbtJoinError::TxVersionMismatch);
+            }
+            if other.unsigned_tx.lock_time != joined.unsigned_tx.lock_time {
+                return Err(PsbtJoinError::LockTimeMismatch);
+            }
+            for input in &other.unsigned_tx.input {
+                if !outpoints.insert(input.previous_output) {
+                    return Err(PsbtJoinError::DuplicateInput {
+                        outpoint: input.previous_output.to_string(),
+                    });
+                }
+            }
+            for (xpub, key_source) in other.xpub {
+                match joined.xpub.get(&xpub) {
+                    Some(existing) if *existing != key_source => {
+                        return Err(PsbtJoinError::InconsistentXpub {
+                            xpub: xpub.to_string(),
+                        })
+                    }
+                    _ => {
+                        joined.xpub.insert(xpub, key_source);
+                    }
+                }
+            }
+            for (key, value) in other.proprietary {
+                if joined.proprietary.get(&key).map_or(false, |v| *v != value) {
+                    return Err(PsbtJoinError::ConflictingGlobalField);
+                }
+                joined.proprietary.insert(key, value);
+            }
+            for (key, value) in other.unknown {
+                if joined.unknown.get(&key).map_or(false, |v| *v != value) {
+                    return Err(PsbtJoinError::ConflictingGlobalField);
+                }
+                joined.unknown.insert(key, value);
+            }
+
+            joined.unsigned_tx.input.extend(other.unsigned_tx.input);
+            joined.unsigned_tx.output.extend(other.unsigned_tx.output);
+            joined.inputs.extend(other.inputs);
+            joined.outputs.extend(other.outputs);
+        }
+
+        if shuffle {
+            let mut rng = thread_rng();
+            let tx = &mut joined.unsigned_tx;
+            let mut inputs: Vec<_> = tx.input.drain(..).zip(joined.inputs.drain(..)).collect();
+            let mut outputs: Vec<_> = tx.output.drain(..).zip(joined.outputs.drain(..)).collect();
+            inputs.shuffle(&mut rng);
+            outputs.shuffle(&mut rng);
+            (tx.input, joined.inputs) = inputs.into_iter().unzip();
+            (tx.output, joined.outputs) = outputs.into_iter().unzip();
+        }
+
+        Ok(Arc::new(Psbt(Mutex::new(joined))))
+    }
+
     pub(crate) fn json_serialize(&self) -> String {
         let psbt = self.0.lock().unwrap();
         serde_json::to_string(psbt.deref()).unwrap()
     }
+
+    pub(crate) fn to_qr_frames(&self, encoding: QrEncoding) -> Result<Vec<String>, QrError> {
+        let psbt_bytes = self.0.lock().unwrap().serialize();
+        encode_frames(&psbt_bytes, QrPayload::Psbt, encoding)
+    }
+
+    pub(crate) fn sighash(
+        &self,
+        input_index: u32,
+        leaf_hash: Option<Vec<u8>>,
+    ) -> Result<InputSighash, SighashError> {
+        let leaf_hash = leaf_hash.as_deref().map(parse_leaf_hash).transpose()?;
+        let psbt = self.0.lock().unwrap();
+        input_sighash(&psbt, input_index as usize, leaf_hash)
+    }
+
+    pub(crate) fn add_ecdsa_signature(
+        &self,
+        input_index: u32,
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<(), SighashError> {
+        let public_key =
+            PublicKey::from_slice(&public_key).map_err(|e| SighashError::InvalidPublicKey {
+                error_message: e.to_string(),
+            })?;
+        let signature = ecdsa::Signature::from_slice(&signature).map_err(|e| {
+            SighashError::InvalidSignature {
+                error_message: e.to_string(),
+            }
+        })?;
+
+        let mut psbt = self.0.lock().unwrap();
+        let index = input_index as usize;
+        let sighash = input_sighash(&psbt, index, None)?;
+        if sighash.kind == SighashKind::Taproot {
+            return Err(SighashError::WrongSignatureType);
+        }
+        if signature.sighash_type.to_u32() != sighash.sighash_type {
+            return Err(SighashError::SighashTypeMismatch {
+                expected: sighash.sighash_type,
+                actual: signature.sighash_type.to_u32(),
+            });
+        }
+        if !input_has_ecdsa_key(&psbt, index, &public_key)? {
+            return Err(SighashError::KeyNotInInput);
+        }
+
+        let message = Message::from_digest_slice(&sighash.message).expect("32-byte sighash");
+        Secp256k1::verification_only()
+            .verify_ecdsa(&message, &signature.signature, &public_key.inner)
+            .map_err(|_| SighashError::SignatureVerification)?;
+
+        psbt.inputs[index]
+            .partial_sigs
+            .insert(public_key, signature);
+        Ok(())
+    }
+
+    pub(crate) fn add_taproot_signature(
+        &self,
+        input_index: u32,
+        signature: Vec<u8>,
+        leaf_hash: Option<Vec<u8>>,
+        public_key: Option<Vec<u8>>,
+    ) -> Result<(), SighashError> {
+        let signature = taproot::Signature::from_slice(&signature).map_err(|e| {
+            SighashError::InvalidSignature {
+                error_message: e.to_string(),
+            }
+        })?;
+        let leaf_hash = leaf_hash.as_deref().map(parse_leaf_hash).transpose()?;
+
+        let mut psbt = self.0.lock().unwrap();
+        let index = input_index as usize;
+        let sighash = input_sighash(&psbt, index, leaf_hash)?;
+        if sighash.kind != SighashKind::Taproot {
+            return Err(SighashError::WrongSignatureType);
+        }
+        if signature.sighash_type as u32 != sighash.sighash_type {
+            return Err(SighashError::SighashTypeMismatch {
+                expected: sighash.sighash_type,
+                actual: signature.sighash_type as u32,
+            });
+        }
+
+        let public_key = public_key
+            .map(|public_key| {
+                XOnlyPublicKey::from_slice(&public_key).map_err(|e| {
+                    SighashError::InvalidPublicKey {
+                        error_message: e.to_string(),
+                    }
+                })
+            })
+            .transpose()?;
+        let key = match (leaf_hash, public_key) {
+            // A key path signature commits to the tweaked output key in the spent script pubkey.
+            (None, public_key) => {
+                let script_pubkey = &spent_output(&psbt, index)?.script_pubkey;
+                let output_key = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..])
+                    .map_err(|_| SighashError::KeyNotInInput)?;
+                if public_key.map_or(false, |key| key != output_key) {
+                    return Err(SighashError::KeyNotInInput);
+                }
+                output_key
+            }
+            (Some(_), None) => return Err(SighashError::MissingPublicKey),
+            (Some(leaf_hash), Some(key)) => {
+                let in_leaf =
+                    psbt.inputs[index]
+                        .tap_scripts
+                        .values()
+                        .any(|(script, leaf_version)| {
+                            TapLeafHash::from_script(script, *leaf_version) == leaf_hash
+                                && script_has_key(script, &key.serialize())
+                        });
+                if !in_leaf {
+                    return Err(SighashError::KeyNotInInput);
+                }
+                key
+            }
+        };
+
+        let message = Message::from_digest_slice(&sighash.message).expect("32-byte sighash");
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature.signature, &message, &key)
+            .map_err(|_| SighashError::SignatureVerification)?;
+
+        let input = &mut psbt.inputs[index];
+        match leaf_hash {
+            None => input.tap_key_sig = Some(signature),
+            Some(leaf_hash) => {
+                input.tap_script_sigs.insert((key, leaf_hash), signature);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_global_xpub(
+        &self,
+        xpub: String,
+        fingerprint: String,
+        path: &DerivationPath,
+    ) -> Result<(), PsbtEditError> {
+        let xpub = parse_xpub(&xpub)?;
+        let fingerprint = parse_fingerprint(fingerprint)?;
+        let path = path.inner_mutex.lock().unwrap().clone();
+        self.0
+            .lock()
+            .unwrap()
+            .xpub
+            .insert(xpub, (fingerprint, path));
+        Ok(())
+    }
+
+    pub(crate) fn remove_global_xpub(&self, xpub: String) -> Result<bool, PsbtEditError> {
+        let xpub = parse_xpub(&xpub)?;
+        Ok(self.0.lock().unwrap().xpub.remove(&xpub).is_some())
+    }
+
+    pub(crate) fn add_bip32_derivation(
+        &self,
+        map: PsbtMap,
+        public_key: Vec<u8>,
+        fingerprint: String,
+        path: &DerivationPath,
+    ) -> Result<(), PsbtEditError> {
+        let public_key = parse_secp256k1_public_key(&public_key)?;
+        let fingerprint = parse_fingerprint(fingerprint)?;
+        let path = path.inner_mutex.lock().unwrap().clone();
+        let mut psbt = self.0.lock().unwrap();
+        bip32_derivation_map(&mut psbt, &map)?.insert(public_key, (fingerprint, path));
+        Ok(())
+    }
+
+    pub(crate) fn remove_bip32_derivation(
+        &self,
+        map: PsbtMap,
+        public_key: Vec<u8>,
+    ) -> Result<bool, PsbtEditError> {
+        let public_key = parse_secp256k1_public_key(&public_key)?;
+        let mut psbt = self.0.lock().unwrap();
+        Ok(bip32_derivation_map(&mut psbt, &map)?
+            .remove(&public_key)
+            .is_some())
+    }
+
+    pub(crate) fn proprietary(
+        &self,
+        map: PsbtMap,
+    ) -> Result<Vec<ProprietaryKeyValue>, PsbtEditError> {
+        let mut psbt = self.0.lock().unwrap();
+        let (proprietary, _) = key_value_maps(&mut psbt, &map)?;
+        Ok(proprietary
+            .iter()
+            .map(|(key, value)| ProprietaryKeyValue {
+                prefix: key.prefix.clone(),
+                subtype: key.subtype,
+                key: key.key.clone(),
+                value: value.clone(),
+            })
+            .collect())
+    }
+
+    pub(crate) fn add_proprietary(
+        &self,
+        map: PsbtMap,
+        pair: ProprietaryKeyValue,
+    ) -> Result<(), PsbtEditError> {
+        let mut psbt = self.0.lock().unwrap();
+        let (proprietary, _) = key_value_maps(&mut psbt, &map)?;
+        let key = ProprietaryKey {
+            prefix: pair.prefix,
+            subtype: pair.subtype,
+            key: pair.key,
+        };
+        proprietary.insert(key, pair.value);
+        Ok(())
+    }
+
+    pub(crate) fn remove_proprietary(
+        &self,
+        map: PsbtMap,
+        prefix: Vec<u8>,
+        subtype: u8,
+        key: Vec<u8>,
+    ) -> Result<bool, PsbtEditError> {
+        let mut psbt = self.0.lock().unwrap();
+        let (proprietary, _) = key_value_maps(&mut psbt, &map)?;
+        let key = ProprietaryKey {
+            prefix,
+            subtype,
+            key,
+        };
+        Ok(proprietary.remove(&key).is_some())
+    }
+
+    pub(crate) fn strip_proprietary(&self, prefix: Vec<u8>) -> u32 {
+        let mut psbt = self.0.lock().unwrap();
+        let psbt = &mut *psbt;
+        let maps = std::iter::once(&mut psbt.proprietary)
+            .chain(psbt.inputs.iter_mut().map(|input| &mut input.proprietary))
+            .chain(
+                psbt.outputs
+                    .iter_mut()
+                    .map(|output| &mut output.proprietary),
+            );
+
+        let mut removed = 0;
+        for proprietary in maps {
+            let before = proprietary.len();
+            proprietary.retain(|key, _| key.prefix != prefix);
+            removed += (before - proprietary.len()) as u32;
+        }
+        removed
+    }
+
+    pub(crate) fn unknown(&self, map: PsbtMap) -> Result<Vec<UnknownKeyValue>, PsbtEditError> {
+        let mut psbt = self.0.lock().unwrap();
+        let (_, unknown) = key_value_maps(&mut psbt, &map)?;
+        Ok(unknown
+            .iter()
+            .map(|(key, value)| UnknownKeyValue {
+                key_type: key.type_value,
+                key: key.key.clone(),
+                value: value.clone(),
+            })
+            .collect())
+    }
+
+    pub(crate) fn add_unknown(
+        &self,
+        map: PsbtMap,
+        pair: UnknownKeyValue,
+    ) -> Result<(), PsbtEditError> {
+        if is_reserved_key_type(&map, pair.key_type) {
+            return Err(PsbtEditError::ReservedKeyType {
+                key_type: pair.key_type,
+            });
+        }
+        let mut psbt = self.0.lock().unwrap();
+        let (_, unknown) = key_value_maps(&mut psbt, &map)?;
+        let key = RawKey {
+            type_value: pair.key_type,
+            key: pair.key,
+        };
+        unknown.insert(key, pair.value);
+        Ok(())
+    }
+
+    pub(crate) fn remove_unknown(
+        &self,
+        map: PsbtMap,
+        key_type: u8,
+        key: Vec<u8>,
+    ) -> Result<bool, PsbtEditError> {
+        let mut psbt = self.0.lock().unwrap();
+        let (_, unknown) = key_value_maps(&mut psbt, &map)?;
+        let key = RawKey {
+            type_value: key_type,
+            key,
+        };
+        Ok(unknown.remove(&key).is_some())
+    }
 }
 
 impl From<BdkPsbt> for Psbt {
@@ -200,6 +1026,399 @@ impl From<BdkPsbt> for Psbt {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header(BdkHeader);
+
+impl Header {
+    pub fn new(header_bytes: Vec<u8>) -> Result<Self, BlockError> {
+        let header: BdkHeader = deserialize(&header_bytes)?;
+        Ok(Header(header))
+    }
+
+    pub fn block_hash(&self) -> BlockHash {
+        self.0.block_hash()
+    }
+
+    pub fn version(&self) -> i32 {
+        self.0.version.to_consensus()
+    }
+
+    pub fn prev_blockhash(&self) -> BlockHash {
+        self.0.prev_blockhash
+    }
+
+    pub fn merkle_root(&self) -> TxMerkleNode {
+        self.0.merkle_root
+    }
+
+    pub fn time(&self) -> u32 {
+        self.0.time
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0.bits.to_consensus()
+    }
+
+    pub fn nonce(&self) -> u32 {
+        self.0.nonce
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        serialize(&self.0)
+    }
+
+    /// Check that the header commits to the target encoded in `required_bits`, as computed by
+    /// the caller from the chain's difficulty adjustments, and that its hash meets that target.
+    pub fn validate_pow(&self, required_bits: u32) -> Result<(), BlockError> {
+        let target = Target::from_compact(CompactTarget::from_consensus(required_bits));
+        self.0.validate_pow(target)?;
+        Ok(())
+    }
+}
+
+impl From<BdkHeader> for Header {
+    fn from(header: BdkHeader) -> Self {
+        Header(header)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block(BdkBlock);
+
+impl Block {
+    pub fn new(block_bytes: Vec<u8>) -> Result<Self, BlockError> {
+        let block: BdkBlock = deserialize(&block_bytes)?;
+        Ok(Block(block))
+    }
+
+    pub fn header(&self) -> Arc<Header> {
+        Arc::new(self.0.header.into())
+    }
+
+    pub fn block_hash(&self) -> BlockHash {
+        self.0.block_hash()
+    }
+
+    pub fn txdata(&self) -> Vec<Arc<Transaction>> {
+        self.0.txdata.iter().map(|tx| Arc::new(tx.into())).collect()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.0.total_size() as u64
+    }
+
+    pub fn weight(&self) -> u64 {
+        self.0.weight().to_wu()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        serialize(&self.0)
+    }
+
+    pub fn check_merkle_root(&self) -> bool {
+        self.0.check_merkle_root()
+    }
+
+    pub fn check_witness_commitment(&self) -> bool {
+        self.0.check_witness_commitment()
+    }
+
+    pub fn merkle_proof(&self, txid: Txid) -> Result<Arc<MerkleBlock>, BlockError> {
+        if !self.0.txdata.iter().any(|tx| tx.compute_txid() == txid) {
+            return Err(BlockError::TxidNotIncluded {
+                txid: txid.to_string(),
+            });
+        }
+        let merkle_block = BdkMerkleBlock::from_block_with_predicate(&self.0, |t| *t == txid);
+        Ok(Arc::new(MerkleBlock(merkle_block)))
+    }
+}
+
+impl From<BdkBlock> for Block {
+    fn from(block: BdkBlock) -> Self {
+        Block(block)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleBlock(BdkMerkleBlock);
+
+impl MerkleBlock {
+    pub fn new(merkle_block_bytes: Vec<u8>) -> Result<Self, BlockError> {
+        let merkle_block: BdkMerkleBlock = deserialize(&merkle_block_bytes)?;
+        Ok(MerkleBlock(merkle_block))
+    }
+
+    pub fn header(&self) -> Arc<Header> {
+        Arc::new(self.0.header.into())
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        serialize(&self.0)
+    }
+
+    /// Return the txids the partial merkle tree commits to, after checking that the tree hashes
+    /// up to the header's merkle root.
+    pub fn extract_matches(&self) -> Result<Vec<Txid>, BlockError> {
+        let mut matches = Vec::new();
+        let mut indexes = Vec::new();
+        self.0
+            .extract_matches(&mut matches, &mut indexes)
+            .map_err(|e| BlockError::InvalidMerkleProof {
+                error_message: e.to_string(),
+            })?;
+        Ok(matches)
+    }
+
+    pub fn verify_inclusion(&self, txid: Txid) -> Result<(), BlockError> {
+        if !self.extract_matches()?.contains(&txid) {
+            return Err(BlockError::TxidNotIncluded {
+                txid: txid.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl From<BdkMerkleBlock> for MerkleBlock {
+    fn from(merkle_block: BdkMerkleBlock) -> Self {
+        MerkleBlock(merkle_block)
+    }
+}
+
+// Standardness limits from Bitcoin Core's policy/policy.h.
+const MAX_STANDARD_TX_VERSION: i32 = 3;
+const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+const MIN_STANDARD_TX_NONWITNESS_SIZE: u64 = 65;
+
+// Signatures commit to the transaction's inputs and outputs, so joining would invalidate them.
+fn ensure_unsigned(psbt: &BdkPsbt) -> Result<(), PsbtJoinError> {
+    let is_signed = psbt.inputs.iter().any(|input| {
+        !input.partial_sigs.is_empty()
+            || input.tap_key_sig.is_some()
+            || !input.tap_script_sigs.is_empty()
+            || input.final_script_sig.is_some()
+            || input.final_script_witness.is_some()
+    });
+    if is_signed {
+        return Err(PsbtJoinError::AlreadySigned);
+    }
+    Ok(())
+}
+
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+const PSBT_PROPRIETARY: u8 = 0xfc;
+const PSBT_IN_LAST_KNOWN_TYPE: u8 = 0x18;
+const PSBT_OUT_LAST_KNOWN_TYPE: u8 = 0x07;
+
+fn parse_xpub(xpub: &str) -> Result<Xpub, PsbtEditError> {
+    Xpub::from_str(xpub).map_err(|e| PsbtEditError::InvalidXpub {
+        error_message: e.to_string(),
+    })
+}
+
+fn parse_fingerprint(fingerprint: String) -> Result<Fingerprint, PsbtEditError> {
+    Fingerprint::from_str(&fingerprint)
+        .map_err(|_| PsbtEditError::InvalidFingerprint { fingerprint })
+}
+
+fn parse_secp256k1_public_key(public_key: &[u8]) -> Result<Secp256k1PublicKey, PsbtEditError> {
+    Secp256k1PublicKey::from_slice(public_key).map_err(|e| PsbtEditError::InvalidPublicKey {
+        error_message: e.to_string(),
+    })
+}
+
+fn parse_public_key(public_key: &[u8]) -> Result<PublicKey, PublicKeyError> {
+    PublicKey::from_slice(public_key).map_err(|e| PublicKeyError::InvalidPublicKey {
+        error_message: e.to_string(),
+    })
+}
+
+fn parse_compressed_public_key(public_key: &[u8]) -> Result<CompressedPublicKey, PublicKeyError> {
+    CompressedPublicKey::try_from(parse_public_key(public_key)?)
+        .map_err(|_| PublicKeyError::UncompressedPublicKey)
+}
+
+fn parse_x_only_public_key(public_key: &[u8]) -> Result<XOnlyPublicKey, PublicKeyError> {
+    XOnlyPublicKey::from_slice(public_key).map_err(|e| PublicKeyError::InvalidPublicKey {
+        error_message: e.to_string(),
+    })
+}
+
+// Key types rust-bitcoin parses into typed fields would be shadowed on the next round trip, so
+// they cannot be stored as unknown pairs.
+fn is_reserved_key_type(map: &PsbtMap, key_type: u8) -> bool {
+    key_type == PSBT_PROPRIETARY
+        || match map {
+            PsbtMap::Global => key_type <= 0x01 || key_type == PSBT_GLOBAL_VERSION,
+            PsbtMap::Input { .. } => key_type <= PSBT_IN_LAST_KNOWN_TYPE,
+            PsbtMap::Output { .. } => key_type <= PSBT_OUT_LAST_KNOWN_TYPE,
+        }
+}
+
+type KeyValueMaps<'a> = (
+    &'a mut BTreeMap<ProprietaryKey, Vec<u8>>,
+    &'a mut BTreeMap<RawKey, Vec<u8>>,
+);
+
+fn key_value_maps<'a>(
+    psbt: &'a mut BdkPsbt,
+    map: &PsbtMap,
+) -> Result<KeyValueMaps<'a>, PsbtEditError> {
+    match *map {
+        PsbtMap::Global => Ok((&mut psbt.proprietary, &mut psbt.unknown)),
+        PsbtMap::Input { index } => psbt
+            .inputs
+            .get_mut(index as usize)
+            .map(|input| (&mut input.proprietary, &mut input.unknown))
+            .ok_or(PsbtEditError::InputIndexOutOfRange { index }),
+        PsbtMap::Output { index } => psbt
+            .outputs
+            .get_mut(index as usize)
+            .map(|output| (&mut output.proprietary, &mut output.unknown))
+            .ok_or(PsbtEditError::OutputIndexOutOfRange { index }),
+    }
+}
+
+fn bip32_derivation_map<'a>(
+    psbt: &'a mut BdkPsbt,
+    map: &PsbtMap,
+) -> Result<&'a mut BTreeMap<Secp256k1PublicKey, KeySource>, PsbtEditError> {
+    match *map {
+        PsbtMap::Global => Err(PsbtEditError::GlobalBip32Derivation),
+        PsbtMap::Input { index } => psbt
+            .inputs
+            .get_mut(index as usize)
+            .map(|input| &mut input.bip32_derivation)
+            .ok_or(PsbtEditError::InputIndexOutOfRange { index }),
+        PsbtMap::Output { index } => psbt
+            .outputs
+            .get_mut(index as usize)
+            .map(|output| &mut output.bip32_derivation)
+            .ok_or(PsbtEditError::OutputIndexOutOfRange { index }),
+    }
+}
+
+fn parse_leaf_hash(leaf_hash: &[u8]) -> Result<TapLeafHash, SighashError> {
+    TapLeafHash::from_slice(leaf_hash).map_err(|_| SighashError::InvalidLeafHash)
+}
+
+fn spent_output(psbt: &BdkPsbt, input_index: usize) -> Result<&BdkTxOut, SighashError> {
+    let input = psbt
+        .inputs
+        .get(input_index)
+        .ok_or(SighashError::InputIndexOutOfRange {
+            input_index: input_index as u32,
+        })?;
+    if let Some(witness_utxo) = &input.witness_utxo {
+        return Ok(witness_utxo);
+    }
+    let vout = psbt.unsigned_tx.input[input_index].previous_output.vout as usize;
+    input
+        .non_witness_utxo
+        .as_ref()
+        .and_then(|tx| tx.output.get(vout))
+        .ok_or(SighashError::MissingUtxo {
+            input_index: input_index as u32,
+        })
+}
+
+fn input_sighash(
+    psbt: &BdkPsbt,
+    input_index: usize,
+    leaf_hash: Option<TapLeafHash>,
+) -> Result<InputSighash, SighashError> {
+    let script_pubkey = &spent_output(psbt, input_index)?.script_pubkey;
+    let mut cache = SighashCache::new(&psbt.unsigned_tx);
+
+    if script_pubkey.is_p2tr() {
+        // Taproot sighashes commit to the outputs spent by every input, not just this one.
+        let prevouts = (0..psbt.inputs.len())
+            .map(|index| spent_output(psbt, index).cloned())
+            .collect::<Result<Vec<_>, _>>()?;
+        let prevouts = Prevouts::All(&prevouts);
+        let sighash_type = psbt.inputs[input_index]
+            .sighash_type
+            .map(|sighash_type| sighash_type.taproot_hash_ty())
+            .transpose()
+            .map_err(|e| SighashError::InvalidSighashType {
+                error_message: e.to_string(),
+            })?
+            .unwrap_or(TapSighashType::Default);
+        let sighash = match leaf_hash {
+            Some(leaf_hash) => cache.taproot_script_spend_signature_hash(
+                input_index,
+                &prevouts,
+                leaf_hash,
+                sighash_type,
+            ),
+            None => cache.taproot_key_spend_signature_hash(input_index, &prevouts, sighash_type),
+        }
+        .map_err(|e| SighashError::Sighash {
+            error_message: e.to_string(),
+        })?;
+
+        return Ok(InputSighash {
+            message: sighash.to_byte_array().to_vec(),
+            kind: SighashKind::Taproot,
+            sighash_type: sighash_type as u32,
+        });
+    }
+
+    if leaf_hash.is_some() {
+        return Err(SighashError::WrongSignatureType);
+    }
+    let (message, sighash_type) = psbt.sighash_ecdsa(input_index, &mut cache)?;
+    let digest: &[u8; 32] = message.as_ref();
+    let redeem_script = psbt.inputs[input_index].redeem_script.as_ref();
+    let is_segwit_v0 = script_pubkey.is_p2wpkh()
+        || script_pubkey.is_p2wsh()
+        || (script_pubkey.is_p2sh()
+            && redeem_script.map_or(false, |script| script.is_p2wpkh() || script.is_p2wsh()));
+
+    Ok(InputSighash {
+        message: digest.to_vec(),
+        kind: if is_segwit_v0 {
+            SighashKind::SegwitV0
+        } else {
+            SighashKind::Legacy
+        },
+        sighash_type: sighash_type.to_u32(),
+    })
+}
+
+fn input_has_ecdsa_key(
+    psbt: &BdkPsbt,
+    input_index: usize,
+    public_key: &PublicKey,
+) -> Result<bool, SighashError> {
+    let input = &psbt.inputs[input_index];
+    if input.bip32_derivation.contains_key(&public_key.inner) {
+        return Ok(true);
+    }
+
+    let mut key_scripts = vec![ScriptBuf::new_p2pkh(&public_key.pubkey_hash())];
+    if let Ok(wpubkey_hash) = public_key.wpubkey_hash() {
+        key_scripts.push(ScriptBuf::new_p2wpkh(&wpubkey_hash));
+    }
+    let script_pubkey = &spent_output(psbt, input_index)?.script_pubkey;
+    let scripts = [
+        Some(script_pubkey),
+        input.redeem_script.as_ref(),
+        input.witness_script.as_ref(),
+    ];
+
+    Ok(scripts.iter().flatten().any(|script| {
+        key_scripts.contains(*script) || script_has_key(script, &public_key.to_bytes())
+    }))
+}
+
+fn script_has_key(script: &BdkScript, key: &[u8]) -> bool {
+    script.instructions().any(|instruction| {
+        matches!(instruction, Ok(Instruction::PushBytes(bytes)) if bytes.as_bytes() == key)
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct TxIn {
     pub previous_output: OutPoint,
@@ -222,6 +1441,20 @@ impl From<&BdkTxIn> for TxIn {
     }
 }
 
+impl From<&TxIn> for BdkTxIn {
+    fn from(tx_in: &TxIn) -> Self {
+        BdkTxIn {
+            previous_output: BdkOutPoint {
+                txid: tx_in.previous_output.txid,
+                vout: tx_in.previous_output.vout,
+            },
+            script_sig: tx_in.script_sig.0.clone(),
+            sequence: Sequence(tx_in.sequence),
+            witness: Witness::from_slice(&tx_in.witness),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TxOut {
     pub value: u64,
@@ -237,10 +1470,307 @@ impl From<&BdkTxOut> for TxOut {
     }
 }
 
+impl From<&TxOut> for BdkTxOut {
+    fn from(tx_out: &TxOut) -> Self {
+        BdkTxOut {
+            value: BdkAmount::from_sat(tx_out.value),
+            script_pubkey: tx_out.script_pubkey.0.clone(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::bitcoin::Address;
     use crate::bitcoin::Network;
+    use crate::bitcoin::Psbt;
+    use crate::bitcoin::Transaction;
+    use crate::bitcoin::{Block, Header, MerkleBlock};
+    use crate::error::{
+        BlockError, ExtractTxError, PublicKeyError, SighashError, TransactionVerifyError,
+    };
+    use crate::types::{
+        AddressType, ExtractTxWarning, InputSighash, PolicyViolation, ProprietaryKeyValue, PsbtMap,
+        SighashKind, UnknownKeyValue,
+    };
+
+    use bitcoin_ffi::{FeeRate, Script};
+
+    use bdk_wallet::bitcoin::absolute::LockTime;
+    use bdk_wallet::bitcoin::consensus::encode::serialize;
+    use bdk_wallet::bitcoin::constants::genesis_block;
+    use bdk_wallet::bitcoin::hashes::Hash;
+    use bdk_wallet::bitcoin::hex::FromHex;
+    use bdk_wallet::bitcoin::key::TapTweak;
+    use bdk_wallet::bitcoin::opcodes::all::OP_CHECKSIG;
+    use bdk_wallet::bitcoin::script::Builder;
+    use bdk_wallet::bitcoin::secp256k1::{Keypair, Message, Secp256k1, SecretKey};
+    use bdk_wallet::bitcoin::sighash::{EcdsaSighashType, SighashCache, TapSighashType};
+    use bdk_wallet::bitcoin::taproot::{LeafVersion, TapLeafHash, TaprootBuilder};
+    use bdk_wallet::bitcoin::transaction::Version;
+    use bdk_wallet::bitcoin::Amount as BdkAmount;
+    use bdk_wallet::bitcoin::OutPoint as BdkOutPoint;
+    use bdk_wallet::bitcoin::Psbt as BdkPsbt;
+    use bdk_wallet::bitcoin::PublicKey as BdkPublicKey;
+    use bdk_wallet::bitcoin::Transaction as BdkTransaction;
+    use bdk_wallet::bitcoin::TxIn as BdkTxIn;
+    use bdk_wallet::bitcoin::TxOut as BdkTxOut;
+    use bdk_wallet::bitcoin::{
+        ecdsa, taproot, Network as BdkNetwork, ScriptBuf, TxMerkleNode, Txid,
+    };
+
+    use std::sync::Arc;
+
+    const PSBT: &str = "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA";
+
+    #[test]
+    fn test_transaction_from_parts() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let unsigned_tx = Transaction::from(psbt.0.lock().unwrap().unsigned_tx.clone());
+
+        let rebuilt = Transaction::from_parts(
+            unsigned_tx.version(),
+            unsigned_tx.lock_time(),
+            unsigned_tx.input(),
+            unsigned_tx.output(),
+        );
+        assert_eq!(rebuilt, unsigned_tx);
+        assert_eq!(rebuilt.compute_txid(), unsigned_tx.compute_txid());
+
+        let modified = rebuilt
+            .with_version(1)
+            .with_lock_time(0)
+            .add_output(unsigned_tx.output()[0].clone());
+        assert_eq!(modified.version(), 1);
+        assert_eq!(modified.lock_time(), 0);
+        assert_eq!(modified.output().len(), 3);
+        assert_ne!(modified.compute_txid(), unsigned_tx.compute_txid());
+    }
+
+    #[test]
+    fn test_transaction_decode() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let unsigned_tx = Transaction::from(psbt.0.lock().unwrap().unsigned_tx.clone());
+
+        let decoded = unsigned_tx.decode(Network::Bitcoin);
+        assert_eq!(decoded.txid, unsigned_tx.compute_txid());
+        assert_eq!(decoded.txid.to_string(), decoded.wtxid.to_string());
+        assert_eq!(decoded.input.len(), 1);
+        assert!(decoded.input[0].witness.is_empty());
+        assert_eq!(decoded.output[0].script_type, "pubkeyhash");
+        assert!(decoded.output[0]
+            .script_pubkey_asm
+            .starts_with("OP_DUP OP_HASH160"));
+        assert!(decoded.output[0].address.as_ref().unwrap().starts_with('1'));
+        assert_eq!(decoded.output[1].script_type, "scripthash");
+        assert!(decoded.output[1].address.as_ref().unwrap().starts_with('3'));
+
+        let json = unsigned_tx.decode_json(Network::Bitcoin);
+        assert!(json.contains(&format!("\"txid\":\"{}\"", decoded.txid)));
+        assert!(json.contains("\"type\":\"pubkeyhash\""));
+    }
+
+    #[test]
+    fn test_transaction_policy_checks() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let unsigned_tx = Transaction::from(psbt.0.lock().unwrap().unsigned_tx.clone());
+        assert!(unsigned_tx.is_standard());
+        let sequence = &unsigned_tx.input_sequences()[0];
+        assert!(!sequence.is_rbf);
+        assert!(sequence.enables_absolute_lock_time);
+        assert!(sequence.relative_lock_time.is_none());
+
+        let mut dust_output = unsigned_tx.output()[0].clone();
+        dust_output.value = 100;
+        let violations = unsigned_tx
+            .with_version(4)
+            .add_output(dust_output)
+            .policy_violations();
+        assert_eq!(
+            violations,
+            vec![
+                PolicyViolation::NonStandardVersion { version: 4 },
+                PolicyViolation::DustOutput {
+                    index: 2,
+                    value: 100
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_psbt_proprietary_and_unknown_fields() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let routing = ProprietaryKeyValue {
+            prefix: b"coordinator".to_vec(),
+            subtype: 0,
+            key: vec![],
+            value: b"cosigner-2".to_vec(),
+        };
+        psbt.add_proprietary(PsbtMap::Global, routing.clone())
+            .unwrap();
+        psbt.add_proprietary(PsbtMap::Input { index: 0 }, routing)
+            .unwrap();
+        psbt.add_unknown(
+            PsbtMap::Output { index: 1 },
+            UnknownKeyValue {
+                key_type: 0x42,
+                key: vec![1, 2, 3],
+                value: vec![4, 5, 6],
+            },
+        )
+        .unwrap();
+
+        let reparsed = Psbt::new(psbt.serialize()).unwrap();
+        assert_eq!(reparsed.proprietary(PsbtMap::Global).unwrap().len(), 1);
+        assert_eq!(
+            reparsed.unknown(PsbtMap::Output { index: 1 }).unwrap()[0].value,
+            vec![4, 5, 6]
+        );
+
+        assert_eq!(reparsed.strip_proprietary(b"coordinator".to_vec()), 2);
+        assert!(reparsed
+            .proprietary(PsbtMap::Input { index: 0 })
+            .unwrap()
+            .is_empty());
+        assert!(reparsed.proprietary(PsbtMap::Input { index: 3 }).is_err());
+        assert!(psbt
+            .add_unknown(
+                PsbtMap::Input { index: 0 },
+                UnknownKeyValue {
+                    key_type: 0x02,
+                    key: vec![],
+                    value: vec![],
+                },
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_address_introspection() {
+        // BIP-173 examples, both paying to the generator point.
+        let generator =
+            Vec::from_hex("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let key_hash = Vec::from_hex("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+
+        let p2wpkh = Address::new(
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert_eq!(p2wpkh.address_type(), AddressType::P2wpkh);
+        assert_eq!(p2wpkh.witness_version(), Some(0));
+        assert_eq!(p2wpkh.witness_program(), Some(key_hash.clone()));
+        assert_eq!(p2wpkh.pubkey_hash(), None);
+        assert!(p2wpkh.is_related_to_pubkey(generator.clone()).unwrap());
+        assert_eq!(p2wpkh.network(), Network::Bitcoin);
+
+        let p2pkh = Address::new(
+            "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r".to_string(),
+            Network::Testnet,
+        )
+        .unwrap();
+        assert_eq!(p2pkh.address_type(), AddressType::P2pkh);
+        assert_eq!(p2pkh.witness_version(), None);
+        assert_eq!(p2pkh.witness_program(), None);
+        assert_eq!(p2pkh.pubkey_hash(), Some(key_hash));
+        assert_eq!(p2pkh.script_hash(), None);
+        assert!(p2pkh.is_related_to_pubkey(generator).unwrap());
+        assert_eq!(p2pkh.network(), Network::Testnet);
+
+        let p2sh = Address::new(
+            "2N83imGV3gPwBzKJQvWJ7cRUY2SpUyU6A5e".to_string(),
+            Network::Regtest,
+        )
+        .unwrap();
+        assert_eq!(p2sh.address_type(), AddressType::P2sh);
+        assert_eq!(p2sh.script_hash().unwrap().len(), 20);
+
+        let p2tr = Address::new(
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr".to_string(),
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert_eq!(p2tr.address_type(), AddressType::P2tr);
+        assert_eq!(p2tr.witness_version(), Some(1));
+        assert_eq!(p2tr.witness_program().unwrap().len(), 32);
+
+        let regtest = Address::new(
+            "bcrt1q39c0vrwpgfjkhasu5mfke9wnym45nydfwaeems".to_string(),
+            Network::Regtest,
+        )
+        .unwrap();
+        assert_eq!(regtest.network(), Network::Regtest);
+
+        assert!(p2tr.is_related_to_pubkey(vec![0x02; 3]).is_err());
+    }
+
+    #[test]
+    fn test_address_constructors() {
+        let generator =
+            Vec::from_hex("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+
+        let p2pkh = Address::p2pkh(generator.clone(), Network::Bitcoin).unwrap();
+        assert_eq!(p2pkh.to_string(), "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+
+        let p2wpkh = Address::p2wpkh(generator.clone(), Network::Bitcoin).unwrap();
+        assert_eq!(
+            p2wpkh.to_string(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+
+        let p2shwpkh = Address::p2shwpkh(generator.clone(), Network::Bitcoin).unwrap();
+        assert_eq!(p2shwpkh.to_string(), "3JvL6Ymt8MVWiCNHC7oWU6nLeHNJKLZGLN");
+
+        // BIP-173 example: a P2PK script paying to the generator point.
+        let mut witness_script = vec![0x21];
+        witness_script.extend_from_slice(&generator);
+        witness_script.push(0xac);
+        let p2wsh = Address::p2wsh(
+            Arc::new(Script(ScriptBuf::from_bytes(witness_script))),
+            Network::Bitcoin,
+        );
+        assert_eq!(
+            p2wsh.to_string(),
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3"
+        );
+
+        // BIP-86 first receiving address, from its internal and its tweaked output key.
+        let internal_key =
+            Vec::from_hex("cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115")
+                .unwrap();
+        let output_key =
+            Vec::from_hex("a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c")
+                .unwrap();
+        let bip86_address = "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr";
+        let p2tr = Address::p2tr(internal_key.clone(), None, Network::Bitcoin).unwrap();
+        assert_eq!(p2tr.to_string(), bip86_address);
+        let p2tr_tweaked = Address::p2tr_tweaked(output_key, Network::Bitcoin).unwrap();
+        assert_eq!(p2tr_tweaked.to_string(), bip86_address);
+        let p2tr_with_script_tree =
+            Address::p2tr(internal_key.clone(), Some(vec![0x01; 32]), Network::Bitcoin).unwrap();
+        assert_ne!(p2tr_with_script_tree.to_string(), bip86_address);
+
+        let mut uncompressed = vec![0x04];
+        uncompressed.extend_from_slice(
+            &Vec::from_hex(concat!(
+                "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+            ))
+            .unwrap(),
+        );
+        assert!(Address::p2pkh(uncompressed.clone(), Network::Bitcoin).is_ok());
+        assert!(matches!(
+            Address::p2wpkh(uncompressed, Network::Bitcoin),
+            Err(PublicKeyError::UncompressedPublicKey)
+        ));
+        assert!(matches!(
+            Address::p2tr(internal_key, Some(vec![0x01; 31]), Network::Bitcoin),
+            Err(PublicKeyError::InvalidMerkleRoot)
+        ));
+    }
 
     #[test]
     fn test_is_valid_for_network() {
@@ -566,4 +2096,543 @@ mod tests {
             "Address should be valid for Regtest"
         );
     }
+
+    /// Keys and a PSBT spending a P2WPKH output, a taproot output through its key path and a
+    /// taproot output whose only leaf is `<script_key> OP_CHECKSIG`.
+    struct SigningFixture {
+        psbt: Psbt,
+        ecdsa_key: SecretKey,
+        internal_key: Keypair,
+        script_key: Keypair,
+        leaf_hash: TapLeafHash,
+    }
+
+    fn signing_fixture() -> SigningFixture {
+        let secp = Secp256k1::new();
+        let ecdsa_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let internal_key = Keypair::from_seckey_slice(&secp, &[2; 32]).unwrap();
+        let script_key = Keypair::from_seckey_slice(&secp, &[3; 32]).unwrap();
+        let (internal_x_only, _) = internal_key.x_only_public_key();
+
+        let leaf = Builder::new()
+            .push_x_only_key(&script_key.x_only_public_key().0)
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, leaf.clone())
+            .unwrap()
+            .finalize(&secp, internal_x_only)
+            .unwrap();
+
+        let ecdsa_public_key = BdkPublicKey::new(ecdsa_key.public_key(&secp));
+        let spent_scripts = [
+            ScriptBuf::new_p2wpkh(&ecdsa_public_key.wpubkey_hash().unwrap()),
+            ScriptBuf::new_p2tr(&secp, internal_x_only, None),
+            ScriptBuf::new_p2tr(&secp, internal_x_only, spend_info.merkle_root()),
+        ];
+        let unsigned_tx = BdkTransaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: (0..3)
+                .map(|vout| BdkTxIn {
+                    previous_output: BdkOutPoint::new(Txid::all_zeros(), vout),
+                    ..BdkTxIn::default()
+                })
+                .collect(),
+            output: vec![BdkTxOut {
+                value: BdkAmount::from_sat(250_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        let mut psbt = BdkPsbt::from_unsigned_tx(unsigned_tx).unwrap();
+        for (input, script_pubkey) in psbt.inputs.iter_mut().zip(spent_scripts) {
+            input.witness_utxo = Some(BdkTxOut {
+                value: BdkAmount::from_sat(100_000),
+                script_pubkey,
+            });
+        }
+        let script = (leaf.clone(), LeafVersion::TapScript);
+        let control_block = spend_info.control_block(&script).unwrap();
+        psbt.inputs[2].tap_scripts.insert(control_block, script);
+
+        SigningFixture {
+            psbt: Psbt::from(psbt),
+            ecdsa_key,
+            internal_key,
+            script_key,
+            leaf_hash: TapLeafHash::from_script(&leaf, LeafVersion::TapScript),
+        }
+    }
+
+    fn ecdsa_signature(
+        sighash: &InputSighash,
+        key: &SecretKey,
+        sighash_type: EcdsaSighashType,
+    ) -> Vec<u8> {
+        let message = Message::from_digest_slice(&sighash.message).unwrap();
+        ecdsa::Signature {
+            signature: Secp256k1::new().sign_ecdsa(&message, key),
+            sighash_type,
+        }
+        .to_vec()
+    }
+
+    fn schnorr_signature(sighash: &InputSighash, key: &Keypair) -> Vec<u8> {
+        let message = Message::from_digest_slice(&sighash.message).unwrap();
+        taproot::Signature {
+            signature: Secp256k1::new().sign_schnorr_no_aux_rand(&message, key),
+            sighash_type: TapSighashType::Default,
+        }
+        .to_vec()
+    }
+
+    #[test]
+    fn test_psbt_sighash() {
+        let fixture = signing_fixture();
+        let psbt = fixture.psbt.0.lock().unwrap().clone();
+
+        let sighash = fixture.psbt.sighash(0, None).unwrap();
+        assert_eq!(sighash.kind, SighashKind::SegwitV0);
+        assert_eq!(sighash.sighash_type, EcdsaSighashType::All.to_u32());
+        let expected = SighashCache::new(&psbt.unsigned_tx)
+            .p2wpkh_signature_hash(
+                0,
+                &psbt.inputs[0].witness_utxo.as_ref().unwrap().script_pubkey,
+                BdkAmount::from_sat(100_000),
+                EcdsaSighashType::All,
+            )
+            .unwrap();
+        assert_eq!(sighash.message, expected.to_byte_array().to_vec());
+
+        let key_path = fixture.psbt.sighash(2, None).unwrap();
+        assert_eq!(key_path.kind, SighashKind::Taproot);
+        assert_eq!(key_path.sighash_type, TapSighashType::Default as u32);
+        let leaf_hash = fixture.leaf_hash.to_byte_array().to_vec();
+        let script_path = fixture.psbt.sighash(2, Some(leaf_hash.clone())).unwrap();
+        assert_eq!(script_path.kind, SighashKind::Taproot);
+        assert_ne!(script_path.message, key_path.message);
+
+        assert!(matches!(
+            fixture.psbt.sighash(0, Some(leaf_hash)),
+            Err(SighashError::WrongSignatureType)
+        ));
+        assert!(matches!(
+            fixture.psbt.sighash(3, None),
+            Err(SighashError::InputIndexOutOfRange { input_index: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_psbt_add_ecdsa_signature() {
+        let fixture = signing_fixture();
+        let secp = Secp256k1::new();
+        let public_key = BdkPublicKey::new(fixture.ecdsa_key.public_key(&secp)).to_bytes();
+        let other_key = SecretKey::from_slice(&[4; 32]).unwrap();
+        let other_public_key = BdkPublicKey::new(other_key.public_key(&secp)).to_bytes();
+        let sighash = fixture.psbt.sighash(0, None).unwrap();
+        let signature = ecdsa_signature(&sighash, &fixture.ecdsa_key, EcdsaSighashType::All);
+
+        assert!(matches!(
+            fixture.psbt.add_ecdsa_signature(
+                0,
+                public_key.clone(),
+                ecdsa_signature(&sighash, &other_key, EcdsaSighashType::All)
+            ),
+            Err(SighashError::SignatureVerification)
+        ));
+        assert!(matches!(
+            fixture.psbt.add_ecdsa_signature(
+                0,
+                other_public_key,
+                ecdsa_signature(&sighash, &other_key, EcdsaSighashType::All)
+            ),
+            Err(SighashError::KeyNotInInput)
+        ));
+        assert!(matches!(
+            fixture.psbt.add_ecdsa_signature(
+                0,
+                public_key.clone(),
+                ecdsa_signature(&sighash, &fixture.ecdsa_key, EcdsaSighashType::None)
+            ),
+            Err(SighashError::SighashTypeMismatch {
+                expected: 1,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            fixture
+                .psbt
+                .add_ecdsa_signature(1, public_key.clone(), signature.clone()),
+            Err(SighashError::WrongSignatureType)
+        ));
+        assert!(matches!(
+            fixture
+                .psbt
+                .add_ecdsa_signature(0, public_key.clone(), vec![0x30, 0x01]),
+            Err(SighashError::InvalidSignature { .. })
+        ));
+        assert!(fixture.psbt.0.lock().unwrap().inputs[0]
+            .partial_sigs
+            .is_empty());
+
+        fixture
+            .psbt
+            .add_ecdsa_signature(0, public_key.clone(), signature.clone())
+            .unwrap();
+        let psbt = fixture.psbt.0.lock().unwrap();
+        let partial_sigs = &psbt.inputs[0].partial_sigs;
+        assert_eq!(partial_sigs.len(), 1);
+        let public_key = BdkPublicKey::from_slice(&public_key).unwrap();
+        assert_eq!(partial_sigs[&public_key].to_vec(), signature);
+    }
+
+    #[test]
+    fn test_psbt_add_taproot_key_path_signature() {
+        let fixture = signing_fixture();
+        let secp = Secp256k1::new();
+        let output_key = fixture.internal_key.tap_tweak(&secp, None).to_inner();
+        let output_public_key = output_key.x_only_public_key().0.serialize().to_vec();
+        let internal_public_key = fixture
+            .internal_key
+            .x_only_public_key()
+            .0
+            .serialize()
+            .to_vec();
+        let sighash = fixture.psbt.sighash(1, None).unwrap();
+        let signature = schnorr_signature(&sighash, &output_key);
+
+        // Key path spends are signed with the tweaked key.
+        assert!(matches!(
+            fixture.psbt.add_taproot_signature(
+                1,
+                schnorr_signature(&sighash, &fixture.internal_key),
+                None,
+                None
+            ),
+            Err(SighashError::SignatureVerification)
+        ));
+        assert!(matches!(
+            fixture.psbt.add_taproot_signature(
+                1,
+                signature.clone(),
+                None,
+                Some(internal_public_key)
+            ),
+            Err(SighashError::KeyNotInInput)
+        ));
+        assert!(matches!(
+            fixture
+                .psbt
+                .add_taproot_signature(0, signature.clone(), None, None),
+            Err(SighashError::WrongSignatureType)
+        ));
+        assert!(fixture.psbt.0.lock().unwrap().inputs[1]
+            .tap_key_sig
+            .is_none());
+
+        fixture
+            .psbt
+            .add_taproot_signature(1, signature.clone(), None, Some(output_public_key))
+            .unwrap();
+        let tap_key_sig = fixture.psbt.0.lock().unwrap().inputs[1].tap_key_sig;
+        assert_eq!(tap_key_sig.unwrap().to_vec(), signature);
+    }
+
+    #[test]
+    fn test_psbt_add_taproot_script_path_signature() {
+        let fixture = signing_fixture();
+        let leaf_hash = Some(fixture.leaf_hash.to_byte_array().to_vec());
+        let (script_x_only, _) = fixture.script_key.x_only_public_key();
+        let script_public_key = script_x_only.serialize().to_vec();
+        let internal_public_key = fixture
+            .internal_key
+            .x_only_public_key()
+            .0
+            .serialize()
+            .to_vec();
+        let sighash = fixture.psbt.sighash(2, leaf_hash.clone()).unwrap();
+        let signature = schnorr_signature(&sighash, &fixture.script_key);
+
+        assert!(matches!(
+            fixture
+                .psbt
+                .add_taproot_signature(2, signature.clone(), leaf_hash.clone(), None),
+            Err(SighashError::MissingPublicKey)
+        ));
+        assert!(matches!(
+            fixture.psbt.add_taproot_signature(
+                2,
+                schnorr_signature(&sighash, &fixture.internal_key),
+                leaf_hash.clone(),
+                Some(internal_public_key)
+            ),
+            Err(SighashError::KeyNotInInput)
+        ));
+        assert!(matches!(
+            fixture.psbt.add_taproot_signature(
+                2,
+                schnorr_signature(&sighash, &fixture.internal_key),
+                leaf_hash.clone(),
+                Some(script_public_key.clone())
+            ),
+            Err(SighashError::SignatureVerification)
+        ));
+        // A signature over the key path sighash does not verify for the leaf.
+        let key_path_sighash = fixture.psbt.sighash(2, None).unwrap();
+        assert!(matches!(
+            fixture.psbt.add_taproot_signature(
+                2,
+                schnorr_signature(&key_path_sighash, &fixture.script_key),
+                leaf_hash.clone(),
+                Some(script_public_key.clone())
+            ),
+            Err(SighashError::SignatureVerification)
+        ));
+        assert!(fixture.psbt.0.lock().unwrap().inputs[2]
+            .tap_script_sigs
+            .is_empty());
+
+        fixture
+            .psbt
+            .add_taproot_signature(2, signature.clone(), leaf_hash, Some(script_public_key))
+            .unwrap();
+        let psbt = fixture.psbt.0.lock().unwrap();
+        let tap_script_sigs = &psbt.inputs[2].tap_script_sigs;
+        assert_eq!(tap_script_sigs.len(), 1);
+        assert_eq!(
+            tap_script_sigs[&(script_x_only, fixture.leaf_hash)].to_vec(),
+            signature
+        );
+    }
+
+    /// A PSBT spending `vouts` of a made up transaction, with one output per input. Each input
+    /// map carries the value of its outpoint, and each output map the script of its output.
+    fn join_part(vouts: std::ops::Range<u32>) -> Arc<Psbt> {
+        let unsigned_tx = BdkTransaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vouts
+                .clone()
+                .map(|vout| BdkTxIn {
+                    previous_output: BdkOutPoint::new(Txid::all_zeros(), vout),
+                    ..BdkTxIn::default()
+                })
+                .collect(),
+            output: vouts
+                .clone()
+                .map(|vout| BdkTxOut {
+                    value: BdkAmount::from_sat(u64::from(vout)),
+                    script_pubkey: ScriptBuf::from_bytes(vec![vout as u8]),
+                })
+                .collect(),
+        };
+        let mut psbt = BdkPsbt::from_unsigned_tx(unsigned_tx).unwrap();
+        for (vout, (input, output)) in vouts.zip(psbt.inputs.iter_mut().zip(&mut psbt.outputs)) {
+            input.witness_utxo = Some(BdkTxOut {
+                value: BdkAmount::from_sat(1_000 + u64::from(vout)),
+                script_pubkey: ScriptBuf::new(),
+            });
+            output.redeem_script = Some(ScriptBuf::from_bytes(vec![vout as u8]));
+        }
+        Arc::new(Psbt::from(psbt))
+    }
+
+    #[test]
+    fn test_psbt_join_shuffle() {
+        for shuffle in [false, true] {
+            let joined = join_part(0..4)
+                .join(vec![join_part(4..8), join_part(8..12)], shuffle)
+                .unwrap();
+            let psbt = joined.0.lock().unwrap();
+            assert_eq!(psbt.inputs.len(), 12);
+            assert_eq!(psbt.outputs.len(), 12);
+
+            let mut vouts = Vec::new();
+            for (tx_in, input) in psbt.unsigned_tx.input.iter().zip(&psbt.inputs) {
+                let vout = tx_in.previous_output.vout;
+                let value = input.witness_utxo.as_ref().unwrap().value;
+                assert_eq!(value, BdkAmount::from_sat(1_000 + u64::from(vout)));
+                vouts.push(vout);
+            }
+            for (tx_out, output) in psbt.unsigned_tx.output.iter().zip(&psbt.outputs) {
+                assert_eq!(output.redeem_script.as_ref(), Some(&tx_out.script_pubkey));
+            }
+
+            if !shuffle {
+                assert_eq!(vouts, (0..12).collect::<Vec<_>>());
+            }
+            vouts.sort_unstable();
+            assert_eq!(vouts, (0..12).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_extract_tx_fee_rate_limit() {
+        // 301 sats for a 468 wu transaction, about 2.6 sat/vB.
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        assert!(psbt
+            .extract_tx_with_fee_rate_limit(&FeeRate::from_sat_per_vb(3).unwrap())
+            .is_ok());
+        assert!(matches!(
+            psbt.extract_tx_with_fee_rate_limit(&FeeRate::from_sat_per_vb(2).unwrap())
+                .map_err(ExtractTxError::from),
+            Err(ExtractTxError::AbsurdFeeRate { fee_rate: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_extract_tx_unchecked_fee_rate() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let extracted = psbt.extract_tx_unchecked_fee_rate();
+        assert!(extracted.warning.is_none());
+        assert_eq!(
+            extracted.transaction.compute_txid(),
+            psbt.extract_tx().unwrap().compute_txid()
+        );
+
+        // Without the previous transaction the fee is unknown, but the transaction still comes
+        // out.
+        let mut bdk_psbt = psbt.0.lock().unwrap().clone();
+        bdk_psbt.inputs[0].non_witness_utxo = None;
+        let extracted = Psbt::from(bdk_psbt).extract_tx_unchecked_fee_rate();
+        assert!(matches!(
+            extracted.warning,
+            Some(ExtractTxWarning::MissingInputValue)
+        ));
+        assert_eq!(extracted.transaction.input().len(), 1);
+
+        // An absurd fee is reported rather than refused.
+        let mut bdk_psbt = psbt.0.lock().unwrap().clone();
+        bdk_psbt.unsigned_tx.output[0].value = BdkAmount::from_sat(1_000);
+        let extracted = Psbt::from(bdk_psbt).extract_tx_unchecked_fee_rate();
+        assert!(matches!(
+            extracted.warning,
+            Some(ExtractTxWarning::AbsurdFeeRate { fee_rate }) if fee_rate > 25_000
+        ));
+    }
+
+    #[test]
+    fn test_transaction_verify() {
+        use crate::bitcoin::TxOut;
+
+        // A mainnet transaction spending a 2-of-3 P2WSH multisig output.
+        let tx = Transaction::new(Vec::from_hex("010000000001011f97548fbbe7a0db7588a66e18d803d0089315aa7d4cc28360b6ec50ef36718a0100000000ffffffff02df1776000000000017a9146c002a686959067f4866b8fb493ad7970290ab728757d29f0000000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d04004730440220565d170eed95ff95027a69b313758450ba84a01224e1f7f130dda46e94d13f8602207bdd20e307f062594022f12ed5017bbf4a055a06aea91c10110a0e3bb23117fc014730440220647d2dc5b15f60bc37dc42618a370b2a1490293f9e5c8464f53ec4fe1dfe067302203598773895b4b16d37485cbe21b337f4e4b650739880098c592553add7dd4355016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000").unwrap()).unwrap();
+        let prevout = |value: u64| TxOut {
+            value,
+            script_pubkey: Arc::new(Script::new(
+                Vec::from_hex(
+                    "0020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d",
+                )
+                .unwrap(),
+            )),
+        };
+
+        assert!(tx.verify(vec![prevout(18_393_430)]).is_ok());
+        // Segwit signatures commit to the spent amount.
+        assert!(matches!(
+            tx.verify(vec![prevout(18_393_429)]),
+            Err(TransactionVerifyError::Script { input_index: 0 })
+        ));
+        assert!(matches!(
+            tx.verify(vec![]),
+            Err(TransactionVerifyError::PrevoutCountMismatch {
+                inputs: 1,
+                prevouts: 0
+            })
+        ));
+
+        // Anyone can spend an OP_TRUE output, without a script sig or witness.
+        let mut spend = tx.0.clone();
+        spend.input[0].witness.clear();
+        let spend = Transaction::from(spend);
+        let op_true = TxOut {
+            value: 18_393_430,
+            script_pubkey: Arc::new(Script::new(vec![0x51])),
+        };
+        assert!(spend.verify(vec![op_true]).is_ok());
+    }
+
+    #[test]
+    fn test_genesis_block() {
+        let genesis = genesis_block(BdkNetwork::Bitcoin);
+        let block = Block::new(serialize(&genesis)).unwrap();
+        assert_eq!(
+            block.block_hash().to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(block.serialize(), serialize(&genesis));
+        assert!(block.check_merkle_root());
+        assert_eq!(block.txdata().len(), 1);
+
+        let header = block.header();
+        assert_eq!(header.bits(), 0x1d00ffff);
+        assert_eq!(header.nonce(), 2_083_236_893);
+        assert_eq!(
+            Header::new(header.serialize()).unwrap().block_hash(),
+            block.block_hash()
+        );
+        assert!(matches!(
+            Header::new(header.serialize()[..79].to_vec()),
+            Err(BlockError::Decode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_header_validate_pow() {
+        let genesis = genesis_block(BdkNetwork::Bitcoin).header;
+        let header = Header::from(genesis);
+        assert!(header.validate_pow(0x1d00ffff).is_ok());
+        // The header must commit to the required target, even an easier one.
+        assert!(matches!(
+            header.validate_pow(0x207fffff),
+            Err(BlockError::BadTarget)
+        ));
+
+        let mut tampered = genesis;
+        tampered.nonce += 1;
+        assert!(matches!(
+            Header::from(tampered).validate_pow(0x1d00ffff),
+            Err(BlockError::BadProofOfWork)
+        ));
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let spend = psbt.0.lock().unwrap().unsigned_tx.clone();
+        let previous = psbt.0.lock().unwrap().inputs[0]
+            .non_witness_utxo
+            .clone()
+            .unwrap();
+
+        let mut block = genesis_block(BdkNetwork::Regtest);
+        block.txdata.extend([previous, spend.clone()]);
+        block.header.merkle_root = block.compute_merkle_root().unwrap();
+        let block = Block::from(block);
+        assert!(block.check_merkle_root());
+
+        let txid = spend.compute_txid();
+        let proof = block.merkle_proof(txid).unwrap();
+        let proof = MerkleBlock::new(proof.serialize()).unwrap();
+        assert_eq!(proof.extract_matches().unwrap(), vec![txid]);
+        assert!(proof.verify_inclusion(txid).is_ok());
+
+        let coinbase = block.txdata()[0].0.compute_txid();
+        assert!(matches!(
+            proof.verify_inclusion(coinbase),
+            Err(BlockError::TxidNotIncluded { .. })
+        ));
+        assert!(matches!(
+            block.merkle_proof(Txid::all_zeros()),
+            Err(BlockError::TxidNotIncluded { .. })
+        ));
+
+        // A proof against a different merkle root is rejected.
+        let mut forged = proof.0.clone();
+        forged.header.merkle_root = TxMerkleNode::all_zeros();
+        assert!(matches!(
+            MerkleBlock::from(forged).extract_matches(),
+            Err(BlockError::InvalidMerkleProof { .. })
+        ));
+    }
 }
@@ -0,0 +1,75 @@
+use bdk_wallet::bitcoin::{OutPoint, Txid};
+use bdk_wallet::rusqlite::types::Type;
+use bdk_wallet::rusqlite::{params, Connection, Error};
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+const CREATE_FROZEN_UTXOS_TABLE: &str = "CREATE TABLE IF NOT EXISTS bdkffi_frozen_utxos (
+    txid TEXT NOT NULL,
+    vout INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    PRIMARY KEY (txid, vout)
+)";
+
+/// Creates the frozen UTXO table if needed and returns every frozen outpoint with its reason.
+pub(crate) fn load_frozen_utxos(db: &Connection) -> Result<BTreeMap<OutPoint, String>, Error> {
+    db.execute(CREATE_FROZEN_UTXOS_TABLE, [])?;
+
+    let mut statement = db.prepare("SELECT txid, vout, reason FROM bdkffi_frozen_utxos")?;
+    let rows = statement.query_map([], |row| {
+        let txid: String = row.get(0)?;
+        let txid = Txid::from_str(&txid)
+            .map_err(|e| Error::FromSqlConversionFailure(0, Type::Text, Box::new(e)))?;
+        Ok((OutPoint::new(txid, row.get(1)?), row.get(2)?))
+    })?;
+    rows.collect()
+}
+
+pub(crate) fn freeze_utxo(db: &Connection, outpoint: &OutPoint, reason: &str) -> Result<(), Error> {
+    db.execute(
+        "INSERT OR REPLACE INTO bdkffi_frozen_utxos (txid, vout, reason) VALUES (?1, ?2, ?3)",
+        params![outpoint.txid.to_string(), outpoint.vout, reason],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn unfreeze_utxo(db: &Connection, outpoint: &OutPoint) -> Result<bool, Error> {
+    let removed = db.execute(
+        "DELETE FROM bdkffi_frozen_utxos WHERE txid = ?1 AND vout = ?2",
+        params![outpoint.txid.to_string(), outpoint.vout],
+    )?;
+    Ok(removed > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coin_control::{freeze_utxo, load_frozen_utxos, unfreeze_utxo};
+
+    use bdk_wallet::bitcoin::{OutPoint, Txid};
+    use bdk_wallet::rusqlite::Connection;
+
+    use std::str::FromStr;
+
+    #[test]
+    fn test_frozen_utxos_persist() {
+        let db = Connection::open_in_memory().unwrap();
+        assert!(load_frozen_utxos(&db).unwrap().is_empty());
+
+        let outpoint = OutPoint::new(
+            Txid::from_str("f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126")
+                .unwrap(),
+            1,
+        );
+        freeze_utxo(&db, &outpoint, "dust attack").unwrap();
+        freeze_utxo(&db, &outpoint, "kyc").unwrap();
+
+        let frozen = load_frozen_utxos(&db).unwrap();
+        assert_eq!(frozen.len(), 1);
+        assert_eq!(frozen.get(&outpoint).unwrap(), "kyc");
+
+        assert!(unfreeze_utxo(&db, &outpoint).unwrap());
+        assert!(!unfreeze_utxo(&db, &outpoint).unwrap());
+        assert!(load_frozen_utxos(&db).unwrap().is_empty());
+    }
+}
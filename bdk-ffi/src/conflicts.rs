@@ -0,0 +1,250 @@
+use bdk_wallet::bitcoin::{Transaction, Txid};
+use bdk_wallet::chain::ChainPosition;
+use bdk_wallet::Wallet;
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+/// Transactions in the wallet's graph that spend at least one of the same outputs as `txid`,
+/// whether or not they are canonical.
+pub(crate) fn conflicts(wallet: &Wallet, txid: Txid) -> Vec<Txid> {
+    let graph = wallet.tx_graph();
+    let tx = match graph.get_tx(txid) {
+        Some(tx) => tx,
+        None => return Vec::new(),
+    };
+    let mut conflicts: Vec<Txid> = graph
+        .direct_conflicts(&tx)
+        .map(|(_, conflict)| conflict)
+        .collect();
+    conflicts.sort_unstable();
+    conflicts.dedup();
+    conflicts
+}
+
+fn canonical_txids(wallet: &Wallet) -> HashSet<Txid> {
+    wallet.transactions().map(|tx| tx.tx_node.txid).collect()
+}
+
+/// The transaction that evicted `txid`. A conflict that was itself replaced is followed to the
+/// canonical transaction that replaced it, and when `txid` has no canonical conflict its
+/// unconfirmed parents are checked, since replacing a parent also evicts its children. Failing
+/// both, the first direct conflict is reported.
+pub(crate) fn replaced_by(wallet: &Wallet, txid: Txid) -> Option<Txid> {
+    replacement(wallet, &canonical_txids(wallet), txid)
+}
+
+fn replacement(wallet: &Wallet, canonical: &HashSet<Txid>, txid: Txid) -> Option<Txid> {
+    if canonical.contains(&txid) {
+        return None;
+    }
+    let tx = wallet.tx_graph().get_tx(txid)?;
+
+    let direct_conflicts = conflicts(wallet, txid);
+    let mut visited = HashSet::from([txid]);
+    let mut queue: VecDeque<Txid> = direct_conflicts.iter().copied().collect();
+    while let Some(conflict) = queue.pop_front() {
+        if !visited.insert(conflict) {
+            continue;
+        }
+        if canonical.contains(&conflict) {
+            return Some(conflict);
+        }
+        queue.extend(conflicts(wallet, conflict));
+    }
+
+    tx.input
+        .iter()
+        .find_map(|input| replacement(wallet, canonical, input.previous_output.txid))
+        .or_else(|| direct_conflicts.first().copied())
+}
+
+/// Whether `txid` is unconfirmed and can be replaced under BIP-125, either because it signals
+/// replaceability itself or because an unconfirmed ancestor does.
+pub(crate) fn is_replaceable(wallet: &Wallet, txid: Txid) -> bool {
+    let tx = match wallet.get_tx(txid) {
+        Some(tx) => tx,
+        None => return false,
+    };
+    match tx.chain_position {
+        ChainPosition::Confirmed(_) => false,
+        ChainPosition::Unconfirmed(_) => {
+            tx.tx_node.tx.is_explicitly_rbf()
+                || tx
+                    .tx_node
+                    .tx
+                    .input
+                    .iter()
+                    .any(|input| is_replaceable(wallet, input.previous_output.txid))
+        }
+    }
+}
+
+/// Non-canonical transactions with the transaction that replaced them and the last time they
+/// were seen in the mempool.
+pub(crate) fn replaced_transactions(wallet: &Wallet) -> Vec<(Arc<Transaction>, u64, Txid)> {
+    let canonical = canonical_txids(wallet);
+    wallet
+        .tx_graph()
+        .full_txs()
+        .filter(|node| !canonical.contains(&node.txid))
+        .filter_map(|node| {
+            let replacement = replacement(wallet, &canonical, node.txid)?;
+            Some((
+                Arc::clone(&node.tx),
+                node.last_seen_unconfirmed.unwrap_or(0),
+                replacement,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conflicts::{conflicts, is_replaceable, replaced_by, replaced_transactions};
+    use crate::test_utils::{confirmed, foreign_outpoint, new_tx, new_wallet, unconfirmed};
+
+    use bdk_wallet::bitcoin::{OutPoint, ScriptBuf, Sequence};
+    use bdk_wallet::KeychainKind;
+
+    #[test]
+    fn test_conflicts_and_replacements() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let change = wallet
+            .reveal_next_address(KeychainKind::Internal)
+            .script_pubkey();
+
+        let funding = new_tx(&[foreign_outpoint(1)], vec![(address, 50_000)]);
+        let funding_outpoint = OutPoint::new(funding.compute_txid(), 0);
+        let update = confirmed(&wallet, &[&funding], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+
+        let original = new_tx(
+            &[funding_outpoint],
+            vec![(ScriptBuf::new(), 30_000), (change.clone(), 19_000)],
+        );
+        let child = new_tx(
+            &[OutPoint::new(original.compute_txid(), 1)],
+            vec![(change.clone(), 18_000)],
+        );
+        // Seen later, so the replacement evicts the original and with it the child.
+        let mut replacement = new_tx(
+            &[funding_outpoint],
+            vec![(ScriptBuf::new(), 30_000), (change, 18_000)],
+        );
+        replacement.input[0].sequence = Sequence::ENABLE_LOCKTIME_NO_RBF;
+        wallet
+            .apply_update_at(unconfirmed(&[&original, &child], 2_000), None)
+            .unwrap();
+        wallet
+            .apply_update_at(unconfirmed(&[&replacement], 3_000), None)
+            .unwrap();
+        let (funding, original, child, replacement) = (
+            funding.compute_txid(),
+            original.compute_txid(),
+            child.compute_txid(),
+            replacement.compute_txid(),
+        );
+
+        assert_eq!(conflicts(&wallet, original), vec![replacement]);
+        assert_eq!(conflicts(&wallet, replacement), vec![original]);
+        assert!(conflicts(&wallet, child).is_empty());
+        assert!(conflicts(&wallet, funding).is_empty());
+
+        assert_eq!(replaced_by(&wallet, original), Some(replacement));
+        assert_eq!(replaced_by(&wallet, child), Some(replacement));
+        assert_eq!(replaced_by(&wallet, replacement), None);
+        assert_eq!(replaced_by(&wallet, foreign_outpoint(2).txid), None);
+
+        // The replacement does not signal RBF and its only parent is confirmed.
+        assert!(!is_replaceable(&wallet, replacement));
+        assert!(!is_replaceable(&wallet, original));
+        assert!(!is_replaceable(&wallet, funding));
+
+        let mut replaced: Vec<_> = replaced_transactions(&wallet)
+            .into_iter()
+            .map(|(tx, last_seen, replaced_by)| (tx.compute_txid(), last_seen, replaced_by))
+            .collect();
+        replaced.sort_unstable();
+        let mut expected = vec![(original, 2_000, replacement), (child, 2_000, replacement)];
+        expected.sort_unstable();
+        assert_eq!(replaced, expected);
+    }
+
+    #[test]
+    fn test_replacement_chain() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+
+        let funding = new_tx(
+            &[foreign_outpoint(1)],
+            vec![(address.clone(), 50_000), (address, 20_000)],
+        );
+        let first_outpoint = OutPoint::new(funding.compute_txid(), 0);
+        let second_outpoint = OutPoint::new(funding.compute_txid(), 1);
+        let update = confirmed(&wallet, &[&funding], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+
+        // Each transaction only conflicts with the one before and after it, so the first one
+        // shares no input with the last.
+        let a = new_tx(&[first_outpoint], vec![(ScriptBuf::new(), 49_000)]);
+        let b = new_tx(
+            &[first_outpoint, second_outpoint],
+            vec![(ScriptBuf::new(), 68_000)],
+        );
+        let c = new_tx(&[second_outpoint], vec![(ScriptBuf::new(), 19_000)]);
+        wallet
+            .apply_update_at(unconfirmed(&[&a], 2_000), None)
+            .unwrap();
+        wallet
+            .apply_update_at(unconfirmed(&[&b], 3_000), None)
+            .unwrap();
+        wallet
+            .apply_update_at(unconfirmed(&[&c], 4_000), None)
+            .unwrap();
+        let (a, b, c) = (a.compute_txid(), b.compute_txid(), c.compute_txid());
+
+        assert_eq!(conflicts(&wallet, a), vec![b]);
+        assert_eq!(replaced_by(&wallet, a), Some(c));
+        assert_eq!(replaced_by(&wallet, b), Some(c));
+        assert_eq!(replaced_by(&wallet, c), None);
+
+        let mut replaced: Vec<_> = replaced_transactions(&wallet)
+            .into_iter()
+            .map(|(tx, _, replaced_by)| (tx.compute_txid(), replaced_by))
+            .collect();
+        replaced.sort_unstable();
+        let mut expected = vec![(a, c), (b, c)];
+        expected.sort_unstable();
+        assert_eq!(replaced, expected);
+    }
+
+    #[test]
+    fn test_is_replaceable_through_ancestor() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let change = wallet
+            .reveal_next_address(KeychainKind::Internal)
+            .script_pubkey();
+
+        let parent = new_tx(&[foreign_outpoint(1)], vec![(address, 50_000)]);
+        let mut child = new_tx(
+            &[OutPoint::new(parent.compute_txid(), 0)],
+            vec![(change, 49_000)],
+        );
+        child.input[0].sequence = Sequence::ENABLE_LOCKTIME_NO_RBF;
+        wallet
+            .apply_update_at(unconfirmed(&[&parent, &child], 2_000), None)
+            .unwrap();
+
+        assert!(is_replaceable(&wallet, parent.compute_txid()));
+        assert!(is_replaceable(&wallet, child.compute_txid()));
+    }
+}
@@ -6,11 +6,14 @@ use bdk_esplora::esplora_client::{Error as BdkEsploraError, Error};
 use bdk_wallet::bitcoin::address::FromScriptError as BdkFromScriptError;
 use bdk_wallet::bitcoin::address::ParseError as BdkParseError;
 use bdk_wallet::bitcoin::bip32::Error as BdkBip32Error;
+use bdk_wallet::bitcoin::block::ValidationError as BdkValidationError;
 use bdk_wallet::bitcoin::consensus::encode::Error as BdkEncodeError;
 use bdk_wallet::bitcoin::hex::DisplayHex;
 use bdk_wallet::bitcoin::psbt::Error as BdkPsbtError;
 use bdk_wallet::bitcoin::psbt::ExtractTxError as BdkExtractTxError;
 use bdk_wallet::bitcoin::psbt::PsbtParseError as BdkPsbtParseError;
+use bdk_wallet::bitcoin::psbt::SignError as BdkSignError;
+use bdk_wallet::bitcoin::script::Error as BdkScriptError;
 use bdk_wallet::chain::local_chain::CannotConnectError as BdkCannotConnectError;
 use bdk_wallet::chain::rusqlite::Error as BdkSqliteError;
 use bdk_wallet::chain::tx_graph::CalculateFeeError as BdkCalculateFeeError;
@@ -118,6 +121,28 @@ pub enum Bip39Error {
     AmbiguousLanguages { languages: String },
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum BlockError {
+    #[error("consensus decoding error: {error_message}")]
+    Decode { error_message: String },
+
+    #[error("block hash does not meet the required target")]
+    BadProofOfWork,
+
+    #[error("header target does not match the required target")]
+    BadTarget,
+
+    #[error("invalid merkle proof: {error_message}")]
+    InvalidMerkleProof { error_message: String },
+
+    #[error("txid {txid} is not included in the block")]
+    TxidNotIncluded { txid: String },
+
+    // This is required because the bdk::bitcoin::block::ValidationError is non-exhaustive
+    #[error("other block validation error")]
+    OtherValidationErr,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum CalculateFeeError {
     #[error("missing transaction output: {out_points:?}")]
@@ -192,6 +217,9 @@ pub enum CreateTxError {
     #[error("reference to an unknown utxo: {outpoint}")]
     UnknownUtxo { outpoint: String },
 
+    #[error("utxo is frozen: {outpoint}")]
+    FrozenUtxo { outpoint: String },
+
     #[error("missing non-witness utxo for outpoint: {outpoint}")]
     MissingNonWitnessUtxo { outpoint: String },
 
@@ -378,6 +406,18 @@ pub enum ExtractTxError {
     OtherExtractTxErr,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum FreezeUtxoError {
+    #[error("the wallet does not own utxo: {outpoint}")]
+    UnknownUtxo { outpoint: String },
+
+    #[error("utxo is already spent: {outpoint}")]
+    SpentUtxo { outpoint: String },
+
+    #[error("sqlite error: {error_message}")]
+    Sqlite { error_message: String },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum FromScriptError {
     #[error("script is not a p2pkh, p2sh or witness program")]
@@ -394,6 +434,24 @@ pub enum FromScriptError {
     OtherFromScriptErr,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryExportError {
+    #[error("transaction {txid} spends wallet outputs the wallet never received")]
+    NegativeBalance { txid: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LabelError {
+    #[error("invalid label record on line {line}: {error_message}")]
+    InvalidRecord { line: u32, error_message: String },
+
+    #[error("unknown label type: {label_type}")]
+    UnknownType { label_type: String },
+
+    #[error("sqlite error: {error_message}")]
+    Sqlite { error_message: String },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum RequestBuilderError {
     #[error("the request has already been consumed")]
@@ -412,6 +470,36 @@ pub enum LoadWithPersistError {
     CouldNotLoad,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum PaymentUriError {
+    #[error("uri does not use the bitcoin: scheme")]
+    InvalidScheme,
+
+    #[error("invalid address: {error_message}")]
+    InvalidAddress { error_message: String },
+
+    #[error("address is not valid for the requested network")]
+    NetworkValidation,
+
+    #[error("invalid amount: {amount}")]
+    InvalidAmount { amount: String },
+
+    #[error("parameter is not a key=value pair: {param}")]
+    MalformedParam { param: String },
+
+    #[error("invalid percent encoding: {value}")]
+    InvalidPercentEncoding { value: String },
+
+    #[error("parameter appears more than once: {key}")]
+    DuplicateParam { key: String },
+
+    #[error("unknown required parameter: {key}")]
+    UnknownRequiredParam { key: String },
+
+    #[error("parameter must be set through its own setter: {key}")]
+    ReservedParam { key: String },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum PersistenceError {
     #[error("writing to persistence error: {error_message}")]
@@ -522,6 +610,54 @@ pub enum PsbtError {
     OtherPsbtErr,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum PsbtEditError {
+    #[error("input index {index} is out of range")]
+    InputIndexOutOfRange { index: u32 },
+
+    #[error("output index {index} is out of range")]
+    OutputIndexOutOfRange { index: u32 },
+
+    #[error("bip32 derivations can only be set on inputs and outputs")]
+    GlobalBip32Derivation,
+
+    #[error("invalid xpub: {error_message}")]
+    InvalidXpub { error_message: String },
+
+    #[error("invalid fingerprint: {fingerprint}")]
+    InvalidFingerprint { fingerprint: String },
+
+    #[error("invalid public key: {error_message}")]
+    InvalidPublicKey { error_message: String },
+
+    #[error("key type {key_type} is reserved and cannot be used for an unknown field")]
+    ReservedKeyType { key_type: u8 },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PsbtJoinError {
+    #[error("psbts with different versions cannot be joined")]
+    PsbtVersionMismatch,
+
+    #[error("transactions with different versions cannot be joined")]
+    TxVersionMismatch,
+
+    #[error("transactions with different lock times cannot be joined")]
+    LockTimeMismatch,
+
+    #[error("input {outpoint} is spent by more than one psbt")]
+    DuplicateInput { outpoint: String },
+
+    #[error("xpub {xpub} has conflicting key sources")]
+    InconsistentXpub { xpub: String },
+
+    #[error("psbts have conflicting values for the same global field")]
+    ConflictingGlobalField,
+
+    #[error("signed psbts cannot be joined")]
+    AlreadySigned,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum PsbtParseError {
     #[error("error in internal psbt data structure: {error_message}")]
@@ -531,6 +667,96 @@ pub enum PsbtParseError {
     Base64Encoding { error_message: String },
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum PublicKeyError {
+    #[error("invalid public key: {error_message}")]
+    InvalidPublicKey { error_message: String },
+
+    #[error("segwit v0 addresses require a compressed public key")]
+    UncompressedPublicKey,
+
+    #[error("invalid taproot merkle root")]
+    InvalidMerkleRoot,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QrError {
+    #[error("bbqr error: {error_message}")]
+    Bbqr { error_message: String },
+
+    #[error("ur error: {error_message}")]
+    Ur { error_message: String },
+
+    #[error("frame is neither a bbqr nor a ur frame")]
+    UnknownFormat,
+
+    #[error("invalid frame header")]
+    InvalidHeader,
+
+    #[error("frame does not belong to the animation being decoded")]
+    MixedFormats,
+
+    #[error("unexpected payload type: {payload_type}")]
+    UnexpectedType { payload_type: String },
+
+    #[error("not all frames have been received yet")]
+    Incomplete,
+
+    #[error("invalid payload: {error_message}")]
+    InvalidPayload { error_message: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    #[error("malformed script: {error_message}")]
+    Malformed { error_message: String },
+
+    #[error("data push of {length} bytes exceeds the 520 byte limit")]
+    PushTooLarge { length: u64 },
+
+    #[error("opcode {opcode} pushes data, use push_slice instead")]
+    InvalidOpcode { opcode: u8 },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SighashError {
+    #[error("input index {input_index} is out of range")]
+    InputIndexOutOfRange { input_index: u32 },
+
+    #[error("missing utxo information for input {input_index}")]
+    MissingUtxo { input_index: u32 },
+
+    #[error("invalid sighash type: {error_message}")]
+    InvalidSighashType { error_message: String },
+
+    #[error("invalid tap leaf hash")]
+    InvalidLeafHash,
+
+    #[error("invalid public key: {error_message}")]
+    InvalidPublicKey { error_message: String },
+
+    #[error("invalid signature: {error_message}")]
+    InvalidSignature { error_message: String },
+
+    #[error("a public key is required for a taproot script path signature")]
+    MissingPublicKey,
+
+    #[error("signature type does not match the input being signed")]
+    WrongSignatureType,
+
+    #[error("sighash type mismatch: expected {expected}, got {actual}")]
+    SighashTypeMismatch { expected: u32, actual: u32 },
+
+    #[error("public key is not used by the input")]
+    KeyNotInInput,
+
+    #[error("signature does not verify against the input sighash")]
+    SignatureVerification,
+
+    #[error("sighash computation error: {error_message}")]
+    Sighash { error_message: String },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SignerError {
     #[error("missing key for signing")]
@@ -616,6 +842,24 @@ pub enum TransactionError {
     OtherTransactionErr,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum TransactionVerifyError {
+    #[error("transaction has {inputs} inputs but {prevouts} prevouts were provided")]
+    PrevoutCountMismatch { inputs: u32, prevouts: u32 },
+
+    #[error("script verification failed for input {input_index}")]
+    Script { input_index: u32 },
+
+    #[error("prevout amount is required to verify input {input_index}")]
+    AmountRequired { input_index: u32 },
+
+    #[error("verification error for input {input_index}: {error_message}")]
+    Consensus {
+        input_index: u32,
+        error_message: String,
+    },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum TxidParseError {
     #[error("invalid txid: {txid}")]
@@ -748,6 +992,24 @@ impl From<BdkBip39Error> for Bip39Error {
     }
 }
 
+impl From<BdkEncodeError> for BlockError {
+    fn from(error: BdkEncodeError) -> Self {
+        BlockError::Decode {
+            error_message: error.to_string(),
+        }
+    }
+}
+
+impl From<BdkValidationError> for BlockError {
+    fn from(error: BdkValidationError) -> Self {
+        match error {
+            BdkValidationError::BadProofOfWork => BlockError::BadProofOfWork,
+            BdkValidationError::BadTarget => BlockError::BadTarget,
+            _ => BlockError::OtherValidationErr,
+        }
+    }
+}
+
 impl From<BdkCalculateFeeError> for CalculateFeeError {
     fn from(error: BdkCalculateFeeError) -> Self {
         match error {
@@ -1151,6 +1413,22 @@ impl From<BdkPsbtParseError> for PsbtParseError {
     }
 }
 
+impl From<BdkScriptError> for ScriptError {
+    fn from(error: BdkScriptError) -> Self {
+        ScriptError::Malformed {
+            error_message: error.to_string(),
+        }
+    }
+}
+
+impl From<BdkSignError> for SighashError {
+    fn from(error: BdkSignError) -> Self {
+        SighashError::Sighash {
+            error_message: error.to_string(),
+        }
+    }
+}
+
 impl From<BdkSignerError> for SignerError {
     fn from(error: BdkSignerError) -> Self {
         match error {
@@ -1202,6 +1480,37 @@ impl From<BdkEncodeError> for TransactionError {
     }
 }
 
+impl TransactionVerifyError {
+    pub(crate) fn from_consensus(input_index: u32, error: bitcoinconsensus::Error) -> Self {
+        match error {
+            bitcoinconsensus::Error::ERR_SCRIPT => TransactionVerifyError::Script { input_index },
+            bitcoinconsensus::Error::ERR_AMOUNT_REQUIRED => {
+                TransactionVerifyError::AmountRequired { input_index }
+            }
+            _ => TransactionVerifyError::Consensus {
+                input_index,
+                error_message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<BdkSqliteError> for FreezeUtxoError {
+    fn from(error: BdkSqliteError) -> Self {
+        FreezeUtxoError::Sqlite {
+            error_message: error.to_string(),
+        }
+    }
+}
+
+impl From<BdkSqliteError> for LabelError {
+    fn from(error: BdkSqliteError) -> Self {
+        LabelError::Sqlite {
+            error_message: error.to_string(),
+        }
+    }
+}
+
 impl From<BdkSqliteError> for SqliteError {
     fn from(error: BdkSqliteError) -> Self {
         SqliteError::Sqlite {
@@ -1217,9 +1526,11 @@ impl From<BdkSqliteError> for SqliteError {
 #[cfg(test)]
 mod test {
     use crate::error::{
-        Bip32Error, Bip39Error, CannotConnectError, DescriptorError, DescriptorKeyError,
-        ElectrumError, EsploraError, ExtractTxError, PersistenceError, PsbtError, PsbtParseError,
-        RequestBuilderError, TransactionError, TxidParseError,
+        Bip32Error, Bip39Error, BlockError, CannotConnectError, DescriptorError,
+        DescriptorKeyError, ElectrumError, EsploraError, ExtractTxError, HistoryExportError,
+        LabelError, PaymentUriError, PersistenceError, PsbtEditError, PsbtError, PsbtJoinError,
+        PsbtParseError, PublicKeyError, QrError, RequestBuilderError, ScriptError, SighashError,
+        TransactionError, TransactionVerifyError, TxidParseError,
     };
     use crate::SignerError;
 
@@ -1316,6 +1627,46 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_error_block() {
+        let cases = vec![
+            (
+                BlockError::Decode {
+                    error_message: "io error".to_string(),
+                },
+                "consensus decoding error: io error",
+            ),
+            (
+                BlockError::BadProofOfWork,
+                "block hash does not meet the required target",
+            ),
+            (
+                BlockError::BadTarget,
+                "header target does not match the required target",
+            ),
+            (
+                BlockError::InvalidMerkleProof {
+                    error_message: "bad tree".to_string(),
+                },
+                "invalid merkle proof: bad tree",
+            ),
+            (
+                BlockError::TxidNotIncluded {
+                    txid: "abcd".to_string(),
+                },
+                "txid abcd is not included in the block",
+            ),
+            (
+                BlockError::OtherValidationErr,
+                "other block validation error",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
     #[test]
     fn test_error_cannot_connect() {
         let error = CannotConnectError::Include { height: 42 };
@@ -1628,6 +1979,106 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_error_history_export() {
+        let error = HistoryExportError::NegativeBalance {
+            txid: "f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "transaction f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126 spends wallet outputs the wallet never received"
+        );
+    }
+
+    #[test]
+    fn test_error_label() {
+        let cases = vec![
+            (
+                LabelError::InvalidRecord {
+                    line: 3,
+                    error_message: "missing ref".to_string(),
+                },
+                "invalid label record on line 3: missing ref",
+            ),
+            (
+                LabelError::UnknownType {
+                    label_type: "utxo".to_string(),
+                },
+                "unknown label type: utxo",
+            ),
+            (
+                LabelError::Sqlite {
+                    error_message: "database is locked".to_string(),
+                },
+                "sqlite error: database is locked",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
+    #[test]
+    fn test_error_payment_uri() {
+        let cases = vec![
+            (
+                PaymentUriError::InvalidScheme,
+                "uri does not use the bitcoin: scheme",
+            ),
+            (
+                PaymentUriError::InvalidAddress {
+                    error_message: "bech32 error".to_string(),
+                },
+                "invalid address: bech32 error",
+            ),
+            (
+                PaymentUriError::NetworkValidation,
+                "address is not valid for the requested network",
+            ),
+            (
+                PaymentUriError::InvalidAmount {
+                    amount: "1.5.0".to_string(),
+                },
+                "invalid amount: 1.5.0",
+            ),
+            (
+                PaymentUriError::MalformedParam {
+                    param: "label".to_string(),
+                },
+                "parameter is not a key=value pair: label",
+            ),
+            (
+                PaymentUriError::InvalidPercentEncoding {
+                    value: "%zz".to_string(),
+                },
+                "invalid percent encoding: %zz",
+            ),
+            (
+                PaymentUriError::DuplicateParam {
+                    key: "amount".to_string(),
+                },
+                "parameter appears more than once: amount",
+            ),
+            (
+                PaymentUriError::UnknownRequiredParam {
+                    key: "req-somethingnew".to_string(),
+                },
+                "unknown required parameter: req-somethingnew",
+            ),
+            (
+                PaymentUriError::ReservedParam {
+                    key: "amount".to_string(),
+                },
+                "parameter must be set through its own setter: amount",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
     #[test]
     fn test_error_psbt() {
         let cases = vec![
@@ -1770,6 +2221,80 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_error_psbt_edit() {
+        let cases = vec![
+            (
+                PsbtEditError::InputIndexOutOfRange { index: 2 },
+                "input index 2 is out of range",
+            ),
+            (
+                PsbtEditError::OutputIndexOutOfRange { index: 5 },
+                "output index 5 is out of range",
+            ),
+            (
+                PsbtEditError::GlobalBip32Derivation,
+                "bip32 derivations can only be set on inputs and outputs",
+            ),
+            (
+                PsbtEditError::InvalidFingerprint {
+                    fingerprint: "zz".to_string(),
+                },
+                "invalid fingerprint: zz",
+            ),
+            (
+                PsbtEditError::ReservedKeyType { key_type: 252 },
+                "key type 252 is reserved and cannot be used for an unknown field",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
+    #[test]
+    fn test_error_psbt_join() {
+        let cases = vec![
+            (
+                PsbtJoinError::PsbtVersionMismatch,
+                "psbts with different versions cannot be joined",
+            ),
+            (
+                PsbtJoinError::TxVersionMismatch,
+                "transactions with different versions cannot be joined",
+            ),
+            (
+                PsbtJoinError::LockTimeMismatch,
+                "transactions with different lock times cannot be joined",
+            ),
+            (
+                PsbtJoinError::DuplicateInput {
+                    outpoint: "txid:0".to_string(),
+                },
+                "input txid:0 is spent by more than one psbt",
+            ),
+            (
+                PsbtJoinError::InconsistentXpub {
+                    xpub: "xpub".to_string(),
+                },
+                "xpub xpub has conflicting key sources",
+            ),
+            (
+                PsbtJoinError::ConflictingGlobalField,
+                "psbts have conflicting values for the same global field",
+            ),
+            (
+                PsbtJoinError::AlreadySigned,
+                "signed psbts cannot be joined",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
     #[test]
     fn test_error_psbt_parse() {
         let cases = vec![
@@ -1792,6 +2317,146 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_error_public_key() {
+        let cases = vec![
+            (
+                PublicKeyError::InvalidPublicKey {
+                    error_message: "malformed public key".to_string(),
+                },
+                "invalid public key: malformed public key",
+            ),
+            (
+                PublicKeyError::UncompressedPublicKey,
+                "segwit v0 addresses require a compressed public key",
+            ),
+            (
+                PublicKeyError::InvalidMerkleRoot,
+                "invalid taproot merkle root",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
+    #[test]
+    fn test_error_qr() {
+        let cases = vec![
+            (
+                QrError::Bbqr {
+                    error_message: "invalid encoding".to_string(),
+                },
+                "bbqr error: invalid encoding",
+            ),
+            (
+                QrError::Ur {
+                    error_message: "invalid checksum".to_string(),
+                },
+                "ur error: invalid checksum",
+            ),
+            (
+                QrError::UnknownFormat,
+                "frame is neither a bbqr nor a ur frame",
+            ),
+            (QrError::InvalidHeader, "invalid frame header"),
+            (
+                QrError::MixedFormats,
+                "frame does not belong to the animation being decoded",
+            ),
+            (
+                QrError::UnexpectedType {
+                    payload_type: "json".to_string(),
+                },
+                "unexpected payload type: json",
+            ),
+            (QrError::Incomplete, "not all frames have been received yet"),
+            (
+                QrError::InvalidPayload {
+                    error_message: "parse failed".to_string(),
+                },
+                "invalid payload: parse failed",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
+    #[test]
+    fn test_error_script() {
+        let cases = vec![
+            (
+                ScriptError::Malformed {
+                    error_message: "unexpected end of script".to_string(),
+                },
+                "malformed script: unexpected end of script",
+            ),
+            (
+                ScriptError::PushTooLarge { length: 521 },
+                "data push of 521 bytes exceeds the 520 byte limit",
+            ),
+            (
+                ScriptError::InvalidOpcode { opcode: 76 },
+                "opcode 76 pushes data, use push_slice instead",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
+    #[test]
+    fn test_error_sighash() {
+        let cases = vec![
+            (
+                SighashError::InputIndexOutOfRange { input_index: 3 },
+                "input index 3 is out of range",
+            ),
+            (
+                SighashError::MissingUtxo { input_index: 0 },
+                "missing utxo information for input 0",
+            ),
+            (SighashError::InvalidLeafHash, "invalid tap leaf hash"),
+            (
+                SighashError::InvalidSignature {
+                    error_message: "malformed signature".to_string(),
+                },
+                "invalid signature: malformed signature",
+            ),
+            (
+                SighashError::MissingPublicKey,
+                "a public key is required for a taproot script path signature",
+            ),
+            (
+                SighashError::WrongSignatureType,
+                "signature type does not match the input being signed",
+            ),
+            (
+                SighashError::SighashTypeMismatch {
+                    expected: 1,
+                    actual: 3,
+                },
+                "sighash type mismatch: expected 1, got 3",
+            ),
+            (
+                SighashError::KeyNotInInput,
+                "public key is not used by the input",
+            ),
+            (
+                SighashError::SignatureVerification,
+                "signature does not verify against the input sighash",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
     #[test]
     fn test_signer_errors() {
         let errors = vec![
@@ -1869,6 +2534,38 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_error_transaction_verify() {
+        let cases = vec![
+            (
+                TransactionVerifyError::PrevoutCountMismatch {
+                    inputs: 2,
+                    prevouts: 1,
+                },
+                "transaction has 2 inputs but 1 prevouts were provided",
+            ),
+            (
+                TransactionVerifyError::Script { input_index: 0 },
+                "script verification failed for input 0",
+            ),
+            (
+                TransactionVerifyError::AmountRequired { input_index: 0 },
+                "prevout amount is required to verify input 0",
+            ),
+            (
+                TransactionVerifyError::Consensus {
+                    input_index: 3,
+                    error_message: "invalid flags".to_string(),
+                },
+                "verification error for input 3: invalid flags",
+            ),
+        ];
+
+        for (error, expected_message) in cases {
+            assert_eq!(error.to_string(), expected_message);
+        }
+    }
+
     #[test]
     fn test_error_txid_parse() {
         let cases = vec![(
@@ -0,0 +1,121 @@
+use bdk_wallet::bitcoin::Txid;
+use bdk_wallet::rusqlite::types::Type;
+use bdk_wallet::rusqlite::{params, Connection, Error};
+use bdk_wallet::Wallet as BdkWallet;
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::str::FromStr;
+
+const CREATE_FIRST_SEEN_TABLE: &str = "CREATE TABLE IF NOT EXISTS bdkffi_first_seen (
+    txid TEXT PRIMARY KEY NOT NULL,
+    first_seen INTEGER NOT NULL
+)";
+
+/// When each wallet transaction was first seen unconfirmed.
+///
+/// bdk_chain only keeps the most recent mempool sighting, so the first one is recorded here the
+/// first time a transaction shows up with one, and written on `Wallet::persist`.
+pub(crate) struct FirstSeen {
+    seen: HashMap<Txid, u64>,
+    unpersisted: BTreeMap<Txid, u64>,
+}
+
+impl FirstSeen {
+    /// Creates the first-seen table if needed and reads every recorded sighting.
+    pub(crate) fn load(db: &Connection) -> Result<Self, Error> {
+        db.execute(CREATE_FIRST_SEEN_TABLE, [])?;
+
+        let mut statement = db.prepare("SELECT txid, first_seen FROM bdkffi_first_seen")?;
+        let rows = statement.query_map([], |row| {
+            let txid: String = row.get(0)?;
+            let txid = Txid::from_str(&txid)
+                .map_err(|e| Error::FromSqlConversionFailure(0, Type::Text, Box::new(e)))?;
+            Ok((txid, row.get(1)?))
+        })?;
+        Ok(FirstSeen {
+            seen: rows.collect::<Result<_, _>>()?,
+            unpersisted: BTreeMap::new(),
+        })
+    }
+
+    pub(crate) fn get(&self, txid: Txid) -> Option<u64> {
+        self.seen.get(&txid).copied()
+    }
+
+    /// Records the current sighting of every transaction in `wallet` that has none recorded yet.
+    pub(crate) fn record(&mut self, wallet: &BdkWallet) {
+        for tx in wallet.tx_graph().full_txs() {
+            if let Some(last_seen) = tx.last_seen_unconfirmed {
+                if let Entry::Vacant(entry) = self.seen.entry(tx.txid) {
+                    entry.insert(last_seen);
+                    self.unpersisted.insert(tx.txid, last_seen);
+                }
+            }
+        }
+    }
+
+    /// Writes the sightings recorded since the last call. Returns whether there were any.
+    pub(crate) fn persist(&mut self, db: &Connection) -> Result<bool, Error> {
+        if self.unpersisted.is_empty() {
+            return Ok(false);
+        }
+        let unpersisted = mem::take(&mut self.unpersisted);
+        let written = db.execute(CREATE_FIRST_SEEN_TABLE, []).and_then(|_| {
+            for (txid, first_seen) in &unpersisted {
+                db.execute(
+                    "INSERT OR IGNORE INTO bdkffi_first_seen (txid, first_seen) VALUES (?1, ?2)",
+                    params![txid.to_string(), first_seen],
+                )?;
+            }
+            Ok(())
+        });
+        if let Err(e) = written {
+            self.unpersisted = unpersisted;
+            return Err(e);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::first_seen::FirstSeen;
+    use crate::test_utils::{foreign_outpoint, new_tx, new_wallet, unconfirmed};
+
+    use bdk_wallet::rusqlite::Connection;
+    use bdk_wallet::KeychainKind;
+
+    #[test]
+    fn test_first_seen() {
+        let db = Connection::open_in_memory().unwrap();
+        let mut first_seen = FirstSeen::load(&db).unwrap();
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let incoming = new_tx(&[foreign_outpoint(1)], vec![(address, 50_000)]);
+        let txid = incoming.compute_txid();
+
+        wallet
+            .apply_update_at(unconfirmed(&[&incoming], 1_000), None)
+            .unwrap();
+        first_seen.record(&wallet);
+        wallet
+            .apply_update_at(unconfirmed(&[&incoming], 2_000), None)
+            .unwrap();
+        first_seen.record(&wallet);
+        assert_eq!(first_seen.get(txid), Some(1_000));
+        assert_eq!(
+            wallet.get_tx(txid).unwrap().tx_node.last_seen_unconfirmed,
+            Some(2_000)
+        );
+
+        assert!(first_seen.persist(&db).unwrap());
+        assert!(!first_seen.persist(&db).unwrap());
+        let loaded = FirstSeen::load(&db).unwrap();
+        assert_eq!(loaded.get(txid), Some(1_000));
+        assert_eq!(loaded.get(foreign_outpoint(1).txid), None);
+    }
+}
@@ -0,0 +1,511 @@
+use crate::balances::{self, civil_from_days, SECONDS_PER_DAY};
+use crate::error::HistoryExportError;
+use crate::labels::LabelStore;
+use crate::types::{HistoryFormat, HistoryLayout, LabelType};
+
+use bdk_wallet::bitcoin::{Transaction, Txid};
+use bdk_wallet::chain::tx_graph::CanonicalTx;
+use bdk_wallet::chain::{ChainPosition, ConfirmationBlockTime};
+use bdk_wallet::Wallet;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+const SATS_PER_BTC: u64 = 100_000_000;
+const CURRENCY: &str = "BTC";
+
+const STANDARD_COLUMNS: [&str; 8] = [
+    "date",
+    "txid",
+    "direction",
+    "amount",
+    "fee",
+    "balance_after",
+    "confirmation_height",
+    "label",
+];
+
+// Koinly's "universal" import layout.
+const KOINLY_COLUMNS: [&str; 12] = [
+    "Date",
+    "Sent Amount",
+    "Sent Currency",
+    "Received Amount",
+    "Received Currency",
+    "Fee Amount",
+    "Fee Currency",
+    "Net Worth Amount",
+    "Net Worth Currency",
+    "Label",
+    "Description",
+    "TxHash",
+];
+
+// CoinTracker's generic CSV import layout.
+const COINTRACKER_COLUMNS: [&str; 8] = [
+    "Date",
+    "Received Quantity",
+    "Received Currency",
+    "Sent Quantity",
+    "Sent Currency",
+    "Fee Amount",
+    "Fee Currency",
+    "Tag",
+];
+
+/// One canonical wallet transaction, as seen by an accountant.
+struct HistoryRow {
+    timestamp: u64,
+    txid: Txid,
+    /// Whether the transaction increased the wallet balance.
+    incoming: bool,
+    /// Net amount received, or net amount sent excluding the fee.
+    amount: u64,
+    /// Only set for outgoing transactions whose inputs are all known to the wallet.
+    fee: Option<u64>,
+    balance_after: u64,
+    confirmation_height: Option<u32>,
+    label: Option<String>,
+}
+
+/// Every canonical transaction, oldest first, followed by unconfirmed ones in the order they were
+/// seen. Transactions replaced through RBF are left out.
+pub(crate) fn export_history(
+    wallet: &Wallet,
+    labels: &LabelStore,
+    format: HistoryFormat,
+    layout: HistoryLayout,
+) -> Result<String, HistoryExportError> {
+    let rows = history_rows(wallet, labels)?;
+    let columns: &[&str] = match layout {
+        HistoryLayout::Standard => &STANDARD_COLUMNS,
+        HistoryLayout::Koinly => &KOINLY_COLUMNS,
+        HistoryLayout::CoinTracker => &COINTRACKER_COLUMNS,
+    };
+    let values: Vec<Vec<Option<String>>> = rows.iter().map(|row| row.values(layout)).collect();
+
+    Ok(match format {
+        HistoryFormat::Csv => to_csv(columns, &values),
+        HistoryFormat::Json => to_json(columns, &values),
+    })
+}
+
+fn history_rows(
+    wallet: &Wallet,
+    labels: &LabelStore,
+) -> Result<Vec<HistoryRow>, HistoryExportError> {
+    let mut balance: i64 = 0;
+    mined_order(wallet)
+        .into_iter()
+        .map(|(tx, confirmation)| {
+            let (sent, received) = wallet.sent_and_received(&tx.tx_node.tx);
+            let delta = received.to_sat() as i64 - sent.to_sat() as i64;
+            balance += delta;
+            let balance_after =
+                u64::try_from(balance).map_err(|_| HistoryExportError::NegativeBalance {
+                    txid: tx.tx_node.txid.to_string(),
+                })?;
+
+            let incoming = delta > 0;
+            let fee = if incoming {
+                None
+            } else {
+                wallet
+                    .calculate_fee(&tx.tx_node.tx)
+                    .ok()
+                    .map(|fee| fee.to_sat())
+            };
+            let (timestamp, confirmation_height) = match (confirmation, &tx.chain_position) {
+                (Some((height, time)), _) => (time, Some(height)),
+                (None, ChainPosition::Confirmed(anchor)) => {
+                    (anchor.confirmation_time, Some(anchor.block_id.height))
+                }
+                (None, ChainPosition::Unconfirmed(last_seen)) => (*last_seen, None),
+            };
+
+            Ok(HistoryRow {
+                timestamp,
+                txid: tx.tx_node.txid,
+                incoming,
+                amount: delta.unsigned_abs().saturating_sub(fee.unwrap_or(0)),
+                fee,
+                balance_after,
+                confirmation_height,
+                label: labels.label_of(LabelType::Tx, &tx.tx_node.txid.to_string()),
+            })
+        })
+        .collect()
+}
+
+type MinedTx<'a> = (
+    CanonicalTx<'a, Arc<Transaction>, ConfirmationBlockTime>,
+    Option<(u32, u64)>,
+);
+
+/// Canonical transactions by the block they were mined in, then unconfirmed ones by when they were
+/// last seen. Ordering by block alone could put a transaction before the one it spends, so a
+/// transaction only comes once every canonical transaction it spends has. Each comes with the
+/// height and time of its block, which unanchored parents take from their confirmed children.
+fn mined_order(wallet: &Wallet) -> Vec<MinedTx<'_>> {
+    let mut confirmations = balances::confirmations(wallet);
+    let mut txs: HashMap<Txid, _> = wallet
+        .transactions()
+        .map(|tx| (tx.tx_node.txid, tx))
+        .collect();
+
+    let mut keys = HashMap::new();
+    let mut waiting_on = HashMap::new();
+    let mut children: HashMap<Txid, Vec<Txid>> = HashMap::new();
+    let mut ready = BTreeSet::new();
+    for (txid, tx) in &txs {
+        let key = match (confirmations.get(txid), &tx.chain_position) {
+            (Some((height, _)), _) => (false, u64::from(*height), *txid),
+            (None, ChainPosition::Confirmed(anchor)) => {
+                (false, u64::from(anchor.block_id.height), *txid)
+            }
+            (None, ChainPosition::Unconfirmed(last_seen)) => (true, *last_seen, *txid),
+        };
+        keys.insert(*txid, key);
+
+        let parents: HashSet<Txid> = tx
+            .tx_node
+            .tx
+            .input
+            .iter()
+            .map(|input| input.previous_output.txid)
+            .filter(|parent| txs.contains_key(parent))
+            .collect();
+        for parent in &parents {
+            children.entry(*parent).or_default().push(*txid);
+        }
+        if parents.is_empty() {
+            ready.insert(key);
+        } else {
+            waiting_on.insert(*txid, parents.len());
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(txs.len());
+    while let Some((_, _, txid)) = ready.pop_first() {
+        for child in children.remove(&txid).unwrap_or_default() {
+            if let Some(waiting) = waiting_on.get_mut(&child) {
+                *waiting -= 1;
+                if *waiting == 0 {
+                    ready.extend(keys.get(&child));
+                }
+            }
+        }
+        if let Some(tx) = txs.remove(&txid) {
+            ordered.push((tx, confirmations.remove(&txid)));
+        }
+    }
+    ordered
+}
+
+impl HistoryRow {
+    fn values(&self, layout: HistoryLayout) -> Vec<Option<String>> {
+        let amount = Some(format_btc(self.amount));
+        let (sent, received) = if self.incoming {
+            (None, amount)
+        } else {
+            (amount, None)
+        };
+        let currency_of = |value: &Option<String>| value.as_ref().map(|_| CURRENCY.to_string());
+        let fee = self.fee.map(format_btc);
+
+        match layout {
+            HistoryLayout::Standard => vec![
+                Some(format_date(self.timestamp, "%Y-%m-%dT%H:%M:%SZ")),
+                Some(self.txid.to_string()),
+                Some(
+                    if self.incoming {
+                        "incoming"
+                    } else {
+                        "outgoing"
+                    }
+                    .to_string(),
+                ),
+                Some(format_btc(self.amount)),
+                fee,
+                Some(format_btc(self.balance_after)),
+                self.confirmation_height.map(|height| height.to_string()),
+                self.label.clone(),
+            ],
+            HistoryLayout::Koinly => vec![
+                Some(format_date(self.timestamp, "%Y-%m-%d %H:%M:%S UTC")),
+                sent.clone(),
+                currency_of(&sent),
+                received.clone(),
+                currency_of(&received),
+                fee.clone(),
+                currency_of(&fee),
+                None,
+                None,
+                None,
+                self.label.clone(),
+                Some(self.txid.to_string()),
+            ],
+            HistoryLayout::CoinTracker => vec![
+                Some(format_date(self.timestamp, "%m/%d/%Y %H:%M:%S")),
+                received.clone(),
+                currency_of(&received),
+                sent.clone(),
+                currency_of(&sent),
+                fee.clone(),
+                currency_of(&fee),
+                None,
+            ],
+        }
+    }
+}
+
+fn format_btc(sats: u64) -> String {
+    format!("{}.{:08}", sats / SATS_PER_BTC, sats % SATS_PER_BTC)
+}
+
+/// Formats a UTC timestamp, supporting the `%Y`, `%m`, `%d`, `%H`, `%M` and `%S` fields.
+fn format_date(timestamp: u64, pattern: &str) -> String {
+    let (year, month, day) = civil_from_days(timestamp / SECONDS_PER_DAY);
+    let seconds = timestamp % SECONDS_PER_DAY;
+    pattern
+        .replace("%Y", &format!("{:04}", year))
+        .replace("%m", &format!("{:02}", month))
+        .replace("%d", &format!("{:02}", day))
+        .replace("%H", &format!("{:02}", seconds / 3_600))
+        .replace("%M", &format!("{:02}", seconds % 3_600 / 60))
+        .replace("%S", &format!("{:02}", seconds % 60))
+}
+
+fn to_csv(columns: &[&str], rows: &[Vec<Option<String>>]) -> String {
+    let mut csv = columns.join(",") + "\n";
+    for row in rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|value| value.as_deref().map(escape_csv).unwrap_or_default())
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// RFC 4180 quoting, needed for labels containing commas, quotes or line breaks.
+fn escape_csv(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// serde_json sorts object keys unless its `preserve_order` feature is enabled, so records are
+// written by hand to keep the column order.
+fn to_json(columns: &[&str], rows: &[Vec<Option<String>>]) -> String {
+    let records: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<String> = columns
+                .iter()
+                .zip(row)
+                .map(|(column, value)| {
+                    let value = value
+                        .clone()
+                        .map_or(serde_json::Value::Null, serde_json::Value::String);
+                    format!("{}:{}", serde_json::Value::from(*column), value)
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        })
+        .collect();
+    format!("[{}]", records.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::HistoryExportError;
+    use crate::history_export::{history_rows, to_csv, to_json, HistoryRow, STANDARD_COLUMNS};
+    use crate::labels::LabelStore;
+    use crate::test_utils::{confirmed, foreign_outpoint, new_tx, new_wallet, unconfirmed};
+    use crate::types::HistoryLayout;
+
+    use bdk_wallet::bitcoin::{Amount, OutPoint, ScriptBuf, TxOut, Txid};
+    use bdk_wallet::rusqlite::Connection;
+    use bdk_wallet::KeychainKind;
+
+    use std::str::FromStr;
+
+    fn outgoing_row() -> HistoryRow {
+        HistoryRow {
+            // 2024-02-29T13:45:00Z
+            timestamp: 1_709_214_300,
+            txid: Txid::from_str(
+                "f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126",
+            )
+            .unwrap(),
+            incoming: false,
+            amount: 150_000_000,
+            fee: Some(1_410),
+            balance_after: 2_500,
+            confirmation_height: Some(832_500),
+            label: Some("Rent, \"March\"".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_history_export_layouts() {
+        let row = outgoing_row();
+        let csv = to_csv(&STANDARD_COLUMNS, &[row.values(HistoryLayout::Standard)]);
+        assert_eq!(
+            csv,
+            "date,txid,direction,amount,fee,balance_after,confirmation_height,label\n\
+             2024-02-29T13:45:00Z,f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126,\
+             outgoing,1.50000000,0.00001410,0.00002500,832500,\"Rent, \"\"March\"\"\"\n"
+        );
+
+        let koinly = row.values(HistoryLayout::Koinly);
+        assert_eq!(koinly[0].as_deref(), Some("2024-02-29 13:45:00 UTC"));
+        assert_eq!(koinly[1].as_deref(), Some("1.50000000"));
+        assert_eq!(koinly[2].as_deref(), Some("BTC"));
+        assert_eq!(koinly[3], None);
+        assert_eq!(koinly[4], None);
+
+        let cointracker = row.values(HistoryLayout::CoinTracker);
+        assert_eq!(cointracker[0].as_deref(), Some("02/29/2024 13:45:00"));
+        assert_eq!(cointracker[1], None);
+        assert_eq!(cointracker[3].as_deref(), Some("1.50000000"));
+        assert_eq!(cointracker[5].as_deref(), Some("0.00001410"));
+    }
+
+    #[test]
+    fn test_history_export_json() {
+        let row = HistoryRow {
+            incoming: true,
+            fee: None,
+            confirmation_height: None,
+            label: None,
+            ..outgoing_row()
+        };
+        let json = to_json(&STANDARD_COLUMNS, &[row.values(HistoryLayout::Standard)]);
+        let positions: Vec<usize> = STANDARD_COLUMNS
+            .iter()
+            .map(|column| json.find(&format!("\"{}\":", column)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        let records: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let record = &records.as_array().unwrap()[0];
+        assert_eq!(record["direction"], "incoming");
+        assert_eq!(record["amount"], "1.50000000");
+        assert_eq!(record["date"], "2024-02-29T13:45:00Z");
+        assert!(record["fee"].is_null());
+        assert!(record["confirmation_height"].is_null());
+        assert!(record["label"].is_null());
+    }
+
+    #[test]
+    fn test_history_rows() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let change = wallet
+            .reveal_next_address(KeychainKind::Internal)
+            .script_pubkey();
+
+        // Only the child is anchored, the parent it spends is still known as unconfirmed.
+        let parent = new_tx(&[foreign_outpoint(1)], vec![(address.clone(), 5_000)]);
+        let child = new_tx(
+            &[OutPoint::new(parent.compute_txid(), 0)],
+            vec![(ScriptBuf::new(), 4_000)],
+        );
+        wallet
+            .apply_update_at(unconfirmed(&[&parent], 500), None)
+            .unwrap();
+        let update = confirmed(&wallet, &[&child], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+
+        let funding = new_tx(&[foreign_outpoint(2)], vec![(address.clone(), 50_000)]);
+        let update = confirmed(&wallet, &[&funding], 2, 2_000);
+        wallet.apply_update(update).unwrap();
+        let payment = new_tx(
+            &[OutPoint::new(funding.compute_txid(), 0)],
+            vec![(ScriptBuf::new(), 30_000), (change, 19_000)],
+        );
+        let update = confirmed(&wallet, &[&payment], 3, 3_000);
+        wallet.apply_update(update).unwrap();
+        // Moves the change to a receive address, so the wallet only loses the fee.
+        let self_transfer = new_tx(
+            &[OutPoint::new(payment.compute_txid(), 1)],
+            vec![(address, 18_500)],
+        );
+        wallet
+            .apply_update_at(unconfirmed(&[&self_transfer], 5_000), None)
+            .unwrap();
+
+        let labels = LabelStore::load(&Connection::open_in_memory().unwrap()).unwrap();
+        let rows: Vec<_> = history_rows(&wallet, &labels)
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                (
+                    row.txid,
+                    row.incoming,
+                    row.amount,
+                    row.fee,
+                    row.balance_after,
+                    row.confirmation_height,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (parent.compute_txid(), true, 5_000, None, 5_000, Some(1)),
+                (child.compute_txid(), false, 4_000, Some(1_000), 0, Some(1)),
+                (funding.compute_txid(), true, 50_000, None, 50_000, Some(2)),
+                (
+                    payment.compute_txid(),
+                    false,
+                    30_000,
+                    Some(1_000),
+                    19_000,
+                    Some(3)
+                ),
+                (
+                    self_transfer.compute_txid(),
+                    false,
+                    0,
+                    Some(500),
+                    18_500,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_rows_missing_parent() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+
+        // The wallet only knows the output the spend consumes, not its transaction.
+        let spend = new_tx(&[foreign_outpoint(1)], vec![(ScriptBuf::new(), 4_000)]);
+        let mut update = confirmed(&wallet, &[&spend], 1, 1_000);
+        update.tx_update.txouts.insert(
+            foreign_outpoint(1),
+            TxOut {
+                value: Amount::from_sat(5_000),
+                script_pubkey: address,
+            },
+        );
+        wallet.apply_update(update).unwrap();
+
+        let labels = LabelStore::load(&Connection::open_in_memory().unwrap()).unwrap();
+        assert!(matches!(
+            history_rows(&wallet, &labels),
+            Err(HistoryExportError::NegativeBalance { txid }) if txid == spend.compute_txid().to_string()
+        ));
+    }
+}
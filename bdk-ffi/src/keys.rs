@@ -54,7 +54,7 @@ impl Display for Mnemonic {
 }
 
 pub(crate) struct DerivationPath {
-    inner_mutex: Mutex<BdkDerivationPath>,
+    pub(crate) inner_mutex: Mutex<BdkDerivationPath>,
 }
 
 impl DerivationPath {
@@ -0,0 +1,277 @@
+use crate::error::LabelError;
+use crate::types::{Label, LabelType};
+
+use bdk_wallet::bitcoin::OutPoint;
+use bdk_wallet::rusqlite::types::Type;
+use bdk_wallet::rusqlite::{params, Connection, Error};
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+const CREATE_LABELS_TABLE: &str = "CREATE TABLE IF NOT EXISTS bdkffi_labels (
+    type TEXT NOT NULL,
+    ref TEXT NOT NULL,
+    label TEXT NOT NULL,
+    origin TEXT,
+    spendable INTEGER,
+    PRIMARY KEY (type, ref)
+)";
+
+impl LabelType {
+    // The `type` values defined by BIP-329.
+    fn as_bip329(&self) -> &'static str {
+        match self {
+            LabelType::Tx => "tx",
+            LabelType::Address => "addr",
+            LabelType::Pubkey => "pubkey",
+            LabelType::Input => "input",
+            LabelType::Output => "output",
+            LabelType::Xpub => "xpub",
+        }
+    }
+
+    fn from_bip329(label_type: &str) -> Option<Self> {
+        match label_type {
+            "tx" => Some(LabelType::Tx),
+            "addr" => Some(LabelType::Address),
+            "pubkey" => Some(LabelType::Pubkey),
+            "input" => Some(LabelType::Input),
+            "output" => Some(LabelType::Output),
+            "xpub" => Some(LabelType::Xpub),
+            _ => None,
+        }
+    }
+}
+
+/// In-memory copy of the labels table, so lookups while listing wallet data never touch SQLite.
+pub(crate) struct LabelStore(BTreeMap<(LabelType, String), Label>);
+
+impl LabelStore {
+    /// Creates the labels table if needed and reads every stored label.
+    pub(crate) fn load(db: &Connection) -> Result<Self, Error> {
+        db.execute(CREATE_LABELS_TABLE, [])?;
+
+        let mut statement =
+            db.prepare("SELECT type, ref, label, origin, spendable FROM bdkffi_labels")?;
+        let rows = statement.query_map([], |row| {
+            let label_type: String = row.get(0)?;
+            // Rows are only ever written from a valid `LabelType`.
+            let label_type = LabelType::from_bip329(&label_type)
+                .ok_or_else(|| Error::FromSqlConversionFailure(0, Type::Text, label_type.into()))?;
+            Ok(Label {
+                label_type,
+                reference: row.get(1)?,
+                label: row.get(2)?,
+                origin: row.get(3)?,
+                spendable: row.get(4)?,
+            })
+        })?;
+
+        let mut labels = BTreeMap::new();
+        for label in rows {
+            let label = label?;
+            labels.insert((label.label_type, label.reference.clone()), label);
+        }
+        Ok(LabelStore(labels))
+    }
+
+    pub(crate) fn get(&self, label_type: LabelType, reference: &str) -> Option<&Label> {
+        self.0.get(&(label_type, reference.to_string()))
+    }
+
+    pub(crate) fn label_of(&self, label_type: LabelType, reference: &str) -> Option<String> {
+        self.get(label_type, reference)
+            .map(|label| label.label.clone())
+    }
+
+    /// Outputs labelled `spendable: false`, which coin selection must leave alone.
+    pub(crate) fn unspendable_outputs(&self) -> impl Iterator<Item = (OutPoint, &Label)> + '_ {
+        self.0
+            .values()
+            .filter(|label| label.label_type == LabelType::Output && label.spendable == Some(false))
+            .filter_map(|label| Some((OutPoint::from_str(&label.reference).ok()?, label)))
+    }
+
+    pub(crate) fn list(&self) -> Vec<Label> {
+        self.0.values().cloned().collect()
+    }
+
+    /// Inserts or replaces labels in a single database transaction.
+    pub(crate) fn set(&mut self, db: &Connection, labels: Vec<Label>) -> Result<(), Error> {
+        let db_tx = db.unchecked_transaction()?;
+        for label in &labels {
+            db_tx.execute(
+                "INSERT OR REPLACE INTO bdkffi_labels (type, ref, label, origin, spendable) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    label.label_type.as_bip329(),
+                    label.reference,
+                    label.label,
+                    label.origin,
+                    label.spendable
+                ],
+            )?;
+        }
+        db_tx.commit()?;
+
+        for label in labels {
+            self.0
+                .insert((label.label_type, label.reference.clone()), label);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn remove(
+        &mut self,
+        db: &Connection,
+        label_type: LabelType,
+        reference: &str,
+    ) -> Result<bool, Error> {
+        let removed = db.execute(
+            "DELETE FROM bdkffi_labels WHERE type = ?1 AND ref = ?2",
+            params![label_type.as_bip329(), reference],
+        )?;
+        self.0.remove(&(label_type, reference.to_string()));
+        Ok(removed > 0)
+    }
+
+    /// One BIP-329 JSON record per line.
+    pub(crate) fn export(&self) -> String {
+        self.0
+            .values()
+            .map(|label| to_json_line(label) + "\n")
+            .collect()
+    }
+}
+
+fn to_json_line(label: &Label) -> String {
+    let json_string = |value: &str| serde_json::Value::String(value.to_string()).to_string();
+
+    let mut line = format!(
+        "{{\"type\":{},\"ref\":{},\"label\":{}",
+        json_string(label.label_type.as_bip329()),
+        json_string(&label.reference),
+        json_string(&label.label)
+    );
+    if let Some(origin) = &label.origin {
+        line.push_str(&format!(",\"origin\":{}", json_string(origin)));
+    }
+    if let Some(spendable) = label.spendable {
+        line.push_str(&format!(",\"spendable\":{}", spendable));
+    }
+    line.push('}');
+    line
+}
+
+/// Parses BIP-329 JSONL, skipping blank lines. Line numbers in errors start at 1.
+pub(crate) fn parse_jsonl(jsonl: &str) -> Result<Vec<Label>, LabelError> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| parse_json_line(index as u32 + 1, line))
+        .collect()
+}
+
+fn parse_json_line(line_number: u32, line: &str) -> Result<Label, LabelError> {
+    let invalid = |error_message: String| LabelError::InvalidRecord {
+        line: line_number,
+        error_message,
+    };
+
+    let record: serde_json::Value =
+        serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+    let string_field = |name: &str| -> Result<Option<String>, LabelError> {
+        match record.get(name) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(serde_json::Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(invalid(format!("{} must be a string", name))),
+        }
+    };
+
+    let label_type = string_field("type")?.ok_or_else(|| invalid("missing type".to_string()))?;
+    let label_type =
+        LabelType::from_bip329(&label_type).ok_or(LabelError::UnknownType { label_type })?;
+    let reference = string_field("ref")?.ok_or_else(|| invalid("missing ref".to_string()))?;
+    let spendable = match record.get("spendable") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::Bool(spendable)) => Some(*spendable),
+        Some(_) => return Err(invalid("spendable must be a boolean".to_string())),
+    };
+
+    Ok(Label {
+        label_type,
+        reference,
+        // BIP-329 allows records without a label, e.g. to only carry the spendable flag.
+        label: string_field("label")?.unwrap_or_default(),
+        origin: string_field("origin")?,
+        spendable,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::LabelError;
+    use crate::labels::{parse_jsonl, LabelStore};
+    use crate::types::LabelType;
+
+    use bdk_wallet::rusqlite::Connection;
+
+    // Records from the BIP-329 examples.
+    const JSONL: &str = r#"{ "type": "tx", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd", "label": "Transaction", "origin": "wpkh([d34db33f/84'/0'/0'])" }
+{ "type": "addr", "ref": "bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c", "label": "Address" }
+
+{ "type": "output", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:0", "label": "Output" , "spendable" : false }
+{ "type": "xpub", "ref": "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8", "label": "Extended Public Key" }
+"#;
+
+    #[test]
+    fn test_labels_round_trip() {
+        let db = Connection::open_in_memory().unwrap();
+        let mut store = LabelStore::load(&db).unwrap();
+        store.set(&db, parse_jsonl(JSONL).unwrap()).unwrap();
+
+        let reloaded = LabelStore::load(&db).unwrap();
+        assert_eq!(reloaded.list().len(), 4);
+        let output = reloaded
+            .get(
+                LabelType::Output,
+                "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:0",
+            )
+            .unwrap();
+        assert_eq!(output.label, "Output");
+        assert_eq!(output.spendable, Some(false));
+
+        let exported = reloaded.export();
+        assert_eq!(exported.lines().count(), 4);
+        assert!(exported.contains(
+            r#"{"type":"addr","ref":"bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c","label":"Address"}"#
+        ));
+        assert_eq!(parse_jsonl(&exported).unwrap().len(), 4);
+
+        assert!(store
+            .remove(
+                &db,
+                LabelType::Address,
+                "bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c"
+            )
+            .unwrap());
+        assert_eq!(LabelStore::load(&db).unwrap().list().len(), 3);
+    }
+
+    #[test]
+    fn test_labels_invalid_jsonl() {
+        assert!(matches!(
+            parse_jsonl("{\"type\": \"tx\", \"ref\": \"abc\"}\nnot json"),
+            Err(LabelError::InvalidRecord { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_jsonl("{\"type\": \"utxo\", \"ref\": \"abc\"}"),
+            Err(LabelError::UnknownType { .. })
+        ));
+        assert!(matches!(
+            parse_jsonl("{\"type\": \"tx\"}"),
+            Err(LabelError::InvalidRecord { line: 1, .. })
+        ));
+    }
+}
@@ -1,15 +1,31 @@
+mod balances;
 mod bitcoin;
+mod coin_control;
+mod conflicts;
 mod descriptor;
 mod electrum;
 mod error;
 mod esplora;
+mod first_seen;
+mod history_export;
 mod keys;
+mod labels;
+mod payment_uri;
+mod qr;
+mod revision;
+mod script;
 mod store;
+#[cfg(test)]
+mod test_utils;
 mod tx_builder;
 mod types;
 mod wallet;
 
 use crate::bitcoin::Address;
+use crate::bitcoin::Block;
+use crate::bitcoin::Header;
+use crate::bitcoin::MerkleBlock;
+use crate::bitcoin::Ntxid;
 use crate::bitcoin::Psbt;
 use crate::bitcoin::Transaction;
 use crate::bitcoin::TxIn;
@@ -19,6 +35,7 @@ use crate::electrum::ElectrumClient;
 use crate::error::AddressParseError;
 use crate::error::Bip32Error;
 use crate::error::Bip39Error;
+use crate::error::BlockError;
 use crate::error::CalculateFeeError;
 use crate::error::CannotConnectError;
 use crate::error::CreateTxError;
@@ -28,40 +45,88 @@ use crate::error::DescriptorKeyError;
 use crate::error::ElectrumError;
 use crate::error::EsploraError;
 use crate::error::ExtractTxError;
+use crate::error::FreezeUtxoError;
 use crate::error::FromScriptError;
+use crate::error::HistoryExportError;
+use crate::error::LabelError;
 use crate::error::LoadWithPersistError;
+use crate::error::PaymentUriError;
 use crate::error::PersistenceError;
+use crate::error::PsbtEditError;
 use crate::error::PsbtError;
+use crate::error::PsbtJoinError;
 use crate::error::PsbtParseError;
+use crate::error::PublicKeyError;
+use crate::error::QrError;
 use crate::error::RequestBuilderError;
+use crate::error::ScriptError;
+use crate::error::SighashError;
 use crate::error::SignerError;
 use crate::error::SqliteError;
 use crate::error::TransactionError;
+use crate::error::TransactionVerifyError;
 use crate::error::TxidParseError;
 use crate::esplora::EsploraClient;
 use crate::keys::DerivationPath;
 use crate::keys::DescriptorPublicKey;
 use crate::keys::DescriptorSecretKey;
 use crate::keys::Mnemonic;
+use crate::payment_uri::PaymentUri;
+use crate::qr::QrDecodeProgress;
+use crate::qr::QrDecoder;
+use crate::qr::QrEncoding;
+use crate::script::ScriptBuilder;
+use crate::script::ScriptInfo;
+use crate::script::ScriptInstruction;
+use crate::script::ScriptType;
 use crate::store::Connection;
 use crate::tx_builder::BumpFeeTxBuilder;
 use crate::tx_builder::TxBuilder;
+use crate::types::AddressBalance;
 use crate::types::AddressInfo;
+use crate::types::AddressType;
 use crate::types::Balance;
+use crate::types::BalanceGranularity;
+use crate::types::BalancePoint;
 use crate::types::BlockId;
 use crate::types::CanonicalTx;
 use crate::types::ChainPosition;
 use crate::types::ConfirmationBlockTime;
+use crate::types::ConfirmationStatus;
+use crate::types::DecodedTransaction;
+use crate::types::DecodedTxIn;
+use crate::types::DecodedTxOut;
+use crate::types::ExtractTxWarning;
+use crate::types::FrozenUtxo;
 use crate::types::FullScanRequest;
 use crate::types::FullScanRequestBuilder;
 use crate::types::FullScanScriptInspector;
+use crate::types::HistoryFormat;
+use crate::types::HistoryLayout;
+use crate::types::InputSighash;
+use crate::types::Label;
+use crate::types::LabelType;
 use crate::types::LocalOutput;
+use crate::types::PaymentUriParam;
+use crate::types::PolicyViolation;
+use crate::types::ProprietaryKeyValue;
+use crate::types::PsbtMap;
+use crate::types::RelativeLockTime;
 use crate::types::ScriptAmount;
 use crate::types::SentAndReceivedValues;
+use crate::types::SequenceInfo;
+use crate::types::SighashKind;
 use crate::types::SyncRequest;
 use crate::types::SyncRequestBuilder;
 use crate::types::SyncScriptInspector;
+use crate::types::TransactionDetails;
+use crate::types::TransactionDirection;
+use crate::types::TransactionFilter;
+use crate::types::TransactionSort;
+use crate::types::UncheckedExtractedTx;
+use crate::types::UnknownKeyValue;
 use crate::types::Update;
+use crate::types::WalletChanges;
 use crate::wallet::Wallet;
 
 use bitcoin_ffi::Amount;
@@ -69,10 +134,56 @@ use bitcoin_ffi::FeeRate;
 use bitcoin_ffi::Network;
 use bitcoin_ffi::OutPoint;
 use bitcoin_ffi::Script;
+use bitcoin_ffi::Txid;
 
+use bdk_wallet::bitcoin::BlockHash;
+use bdk_wallet::bitcoin::TxMerkleNode;
+use bdk_wallet::bitcoin::Wtxid;
 use bdk_wallet::keys::bip39::WordCount;
 use bdk_wallet::tx_builder::ChangeSpendPolicy;
 use bdk_wallet::ChangeSet;
 use bdk_wallet::KeychainKind;
 
+use std::str::FromStr;
+
+impl UniffiCustomTypeConverter for BlockHash {
+    type Builtin = String;
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(BlockHash::from_str(&val)?)
+    }
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.to_string()
+    }
+}
+
+impl UniffiCustomTypeConverter for Wtxid {
+    type Builtin = String;
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(Wtxid::from_str(&val)?)
+    }
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.to_string()
+    }
+}
+
+impl UniffiCustomTypeConverter for Ntxid {
+    type Builtin = String;
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(Ntxid::from_str(&val)?)
+    }
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.to_string()
+    }
+}
+
+impl UniffiCustomTypeConverter for TxMerkleNode {
+    type Builtin = String;
+    fn into_custom(val: Self::Builtin) -> uniffi::Result<Self> {
+        Ok(TxMerkleNode::from_str(&val)?)
+    }
+    fn from_custom(obj: Self) -> Self::Builtin {
+        obj.to_string()
+    }
+}
+
 uniffi::include_scaffolding!("bdk");
@@ -0,0 +1,437 @@
+use crate::bitcoin::Address;
+use crate::error::PaymentUriError;
+use crate::types::{PaymentUriParam, ScriptAmount};
+
+use bitcoin_ffi::{Amount, Script};
+
+use bdk_wallet::bitcoin::address::NetworkUnchecked;
+use bdk_wallet::bitcoin::amount::Denomination;
+use bdk_wallet::bitcoin::Address as BdkAddress;
+use bdk_wallet::bitcoin::Amount as BdkAmount;
+use bdk_wallet::bitcoin::Network;
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+const SCHEME: &str = "bitcoin:";
+const REQUIRED_PREFIX: &str = "req-";
+// Parameters with their own field, which must not also appear among the extra parameters.
+const KNOWN_PARAMS: [&str; 5] = ["amount", "label", "message", "lightning", "pj"];
+
+/// A BIP-21 payment request, as found in `bitcoin:` URIs and payment QR codes.
+#[derive(Clone, Debug)]
+pub struct PaymentUri {
+    address: BdkAddress,
+    amount: Option<BdkAmount>,
+    label: Option<String>,
+    message: Option<String>,
+    lightning: Option<String>,
+    payjoin: Option<String>,
+    extra_params: Vec<(String, String)>,
+}
+
+impl PaymentUri {
+    pub fn new(uri: String, network: Network) -> Result<Self, PaymentUriError> {
+        let scheme_length = SCHEME.len();
+        let scheme = uri.get(..scheme_length);
+        if !scheme.map_or(false, |scheme| scheme.eq_ignore_ascii_case(SCHEME)) {
+            return Err(PaymentUriError::InvalidScheme);
+        }
+
+        let (address, query) = match uri[scheme_length..].split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (&uri[scheme_length..], None),
+        };
+        let address = address
+            .parse::<BdkAddress<NetworkUnchecked>>()
+            .map_err(|e| PaymentUriError::InvalidAddress {
+                error_message: e.to_string(),
+            })?
+            .require_network(network)
+            .map_err(|_| PaymentUriError::NetworkValidation)?;
+
+        let mut payment_uri = PaymentUri::from_bdk_address(address);
+        for param in query.into_iter().flat_map(|query| query.split('&')) {
+            if param.is_empty() {
+                continue;
+            }
+            let (key, value) =
+                param
+                    .split_once('=')
+                    .ok_or_else(|| PaymentUriError::MalformedParam {
+                        param: param.to_string(),
+                    })?;
+            let key = percent_decode(key)?;
+            let value = percent_decode(value)?;
+            payment_uri.set_param(key, value)?;
+        }
+
+        Ok(payment_uri)
+    }
+
+    pub fn from_address(address: Arc<Address>) -> Self {
+        PaymentUri::from_bdk_address(address.as_ref().clone().into())
+    }
+
+    pub fn address(&self) -> Arc<Address> {
+        Arc::new(self.address.clone().into())
+    }
+
+    pub fn amount(&self) -> Option<Arc<Amount>> {
+        self.amount.map(|amount| Arc::new(Amount::from(amount)))
+    }
+
+    pub fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    pub fn message(&self) -> Option<String> {
+        self.message.clone()
+    }
+
+    pub fn lightning(&self) -> Option<String> {
+        self.lightning.clone()
+    }
+
+    pub fn payjoin(&self) -> Option<String> {
+        self.payjoin.clone()
+    }
+
+    pub fn extra_params(&self) -> Vec<PaymentUriParam> {
+        self.extra_params
+            .iter()
+            .map(|(key, value)| PaymentUriParam {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    pub fn with_amount(&self, amount: Arc<Amount>) -> Arc<Self> {
+        Arc::new(PaymentUri {
+            amount: Some(amount.0),
+            ..self.clone()
+        })
+    }
+
+    pub fn with_label(&self, label: String) -> Arc<Self> {
+        Arc::new(PaymentUri {
+            label: Some(label),
+            ..self.clone()
+        })
+    }
+
+    pub fn with_message(&self, message: String) -> Arc<Self> {
+        Arc::new(PaymentUri {
+            message: Some(message),
+            ..self.clone()
+        })
+    }
+
+    pub fn with_lightning(&self, lightning: String) -> Arc<Self> {
+        Arc::new(PaymentUri {
+            lightning: Some(lightning),
+            ..self.clone()
+        })
+    }
+
+    pub fn with_payjoin(&self, payjoin: String) -> Arc<Self> {
+        Arc::new(PaymentUri {
+            payjoin: Some(payjoin),
+            ..self.clone()
+        })
+    }
+
+    /// Adds a parameter this type has no field for. Known parameters must go through their own
+    /// setter, and `req-` parameters would make the URI unreadable for this parser.
+    pub fn with_extra_param(
+        &self,
+        key: String,
+        value: String,
+    ) -> Result<Arc<Self>, PaymentUriError> {
+        if KNOWN_PARAMS.contains(&key.as_str()) {
+            return Err(PaymentUriError::ReservedParam { key });
+        }
+        if key.starts_with(REQUIRED_PREFIX) {
+            return Err(PaymentUriError::UnknownRequiredParam { key });
+        }
+
+        let mut extra_params = self.extra_params.clone();
+        extra_params.push((key, value));
+        Ok(Arc::new(PaymentUri {
+            extra_params,
+            ..self.clone()
+        }))
+    }
+
+    /// The recipient for a `TxBuilder`, or `None` if the request does not specify an amount.
+    pub fn script_amount(&self) -> Option<ScriptAmount> {
+        self.amount.map(|amount| ScriptAmount {
+            script: Arc::new(Script(self.address.script_pubkey())),
+            amount: Arc::new(Amount::from(amount)),
+        })
+    }
+
+    fn from_bdk_address(address: BdkAddress) -> Self {
+        PaymentUri {
+            address,
+            amount: None,
+            label: None,
+            message: None,
+            lightning: None,
+            payjoin: None,
+            extra_params: Vec::new(),
+        }
+    }
+
+    fn set_param(&mut self, key: String, value: String) -> Result<(), PaymentUriError> {
+        let slot = match key.as_str() {
+            "amount" => {
+                if self.amount.is_some() {
+                    return Err(PaymentUriError::DuplicateParam { key });
+                }
+                let amount = BdkAmount::from_str_in(&value, Denomination::Bitcoin)
+                    .map_err(|_| PaymentUriError::InvalidAmount { amount: value })?;
+                self.amount = Some(amount);
+                return Ok(());
+            }
+            "label" => &mut self.label,
+            "message" => &mut self.message,
+            "lightning" => &mut self.lightning,
+            "pj" => &mut self.payjoin,
+            _ if key.starts_with(REQUIRED_PREFIX) => {
+                return Err(PaymentUriError::UnknownRequiredParam { key });
+            }
+            _ => {
+                self.extra_params.push((key, value));
+                return Ok(());
+            }
+        };
+        if slot.is_some() {
+            return Err(PaymentUriError::DuplicateParam { key });
+        }
+        *slot = Some(value);
+        Ok(())
+    }
+}
+
+impl Display for PaymentUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", SCHEME, self.address)?;
+
+        let amount = self
+            .amount
+            .map(|amount| amount.to_string_in(Denomination::Bitcoin));
+        let params = [
+            ("amount", amount.as_ref()),
+            ("label", self.label.as_ref()),
+            ("message", self.message.as_ref()),
+            ("lightning", self.lightning.as_ref()),
+            ("pj", self.payjoin.as_ref()),
+        ];
+        let params = params
+            .iter()
+            .filter_map(|(key, value)| value.map(|value| (*key, value.as_str())))
+            .chain(
+                self.extra_params
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            );
+
+        for (index, (key, value)) in params.enumerate() {
+            let separator = if index == 0 { '?' } else { '&' };
+            write!(
+                f,
+                "{}{}={}",
+                separator,
+                percent_encode(key),
+                percent_encode(value)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> Result<String, PaymentUriError> {
+    let invalid = || PaymentUriError::InvalidPercentEncoding {
+        value: value.to_string(),
+    };
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = value.get(index + 1..index + 3).ok_or_else(invalid)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::PaymentUriError;
+    use crate::payment_uri::PaymentUri;
+
+    use bdk_wallet::bitcoin::Network;
+
+    const ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    #[test]
+    fn test_payment_uri_parse() {
+        let uri = format!(
+            "BITCOIN:{}?amount=0.0005&label=Luke%20Jr&message=Donation%20for%20project%20xyz&pj=https://example.com/pj&pjos=0",
+            ADDRESS
+        );
+        let payment_uri = PaymentUri::new(uri, Network::Testnet).unwrap();
+
+        assert_eq!(payment_uri.address().to_string(), ADDRESS);
+        assert_eq!(payment_uri.amount().unwrap().0.to_sat(), 50_000);
+        assert_eq!(payment_uri.label().unwrap(), "Luke Jr");
+        assert_eq!(payment_uri.message().unwrap(), "Donation for project xyz");
+        assert_eq!(payment_uri.payjoin().unwrap(), "https://example.com/pj");
+        assert!(payment_uri.lightning().is_none());
+
+        let extra_params = payment_uri.extra_params();
+        assert_eq!(extra_params.len(), 1);
+        assert_eq!(extra_params[0].key, "pjos");
+        assert_eq!(extra_params[0].value, "0");
+
+        let script_amount = payment_uri.script_amount().unwrap();
+        assert_eq!(
+            script_amount.script.0,
+            payment_uri.address().script_pubkey().0
+        );
+        assert_eq!(script_amount.amount.0.to_sat(), 50_000);
+    }
+
+    #[test]
+    fn test_payment_uri_round_trip() {
+        let payment_uri = PaymentUri::new(format!("bitcoin:{}", ADDRESS), Network::Signet)
+            .unwrap()
+            .with_label("Coffee & cake".to_string())
+            .with_lightning("lntb1pexample".to_string());
+        assert!(payment_uri.script_amount().is_none());
+
+        let uri = payment_uri.to_string();
+        assert_eq!(
+            uri,
+            format!(
+                "bitcoin:{}?label=Coffee%20%26%20cake&lightning=lntb1pexample",
+                ADDRESS
+            )
+        );
+        let parsed = PaymentUri::new(uri, Network::Signet).unwrap();
+        assert_eq!(parsed.label().unwrap(), "Coffee & cake");
+        assert_eq!(parsed.lightning().unwrap(), "lntb1pexample");
+    }
+
+    #[test]
+    fn test_payment_uri_errors() {
+        let cases = vec![
+            (
+                format!("litecoin:{}", ADDRESS),
+                Network::Testnet,
+                PaymentUriError::InvalidScheme,
+            ),
+            (
+                format!("bitcoin:{}", ADDRESS),
+                Network::Bitcoin,
+                PaymentUriError::NetworkValidation,
+            ),
+            (
+                format!("bitcoin:{}?amount=1.5.0", ADDRESS),
+                Network::Testnet,
+                PaymentUriError::InvalidAmount {
+                    amount: "1.5.0".to_string(),
+                },
+            ),
+            (
+                format!("bitcoin:{}?label=a&label=b", ADDRESS),
+                Network::Testnet,
+                PaymentUriError::DuplicateParam {
+                    key: "label".to_string(),
+                },
+            ),
+            (
+                format!("bitcoin:{}?req-somethingnew=1", ADDRESS),
+                Network::Testnet,
+                PaymentUriError::UnknownRequiredParam {
+                    key: "req-somethingnew".to_string(),
+                },
+            ),
+            (
+                format!("bitcoin:{}?label", ADDRESS),
+                Network::Testnet,
+                PaymentUriError::MalformedParam {
+                    param: "label".to_string(),
+                },
+            ),
+            (
+                "bitcoin\u{e9}:".to_string(),
+                Network::Testnet,
+                PaymentUriError::InvalidScheme,
+            ),
+            (
+                "\u{20bf}\u{20bf}\u{20bf}".to_string(),
+                Network::Testnet,
+                PaymentUriError::InvalidScheme,
+            ),
+            (
+                format!("bitcoin:{}?label=%zz", ADDRESS),
+                Network::Testnet,
+                PaymentUriError::InvalidPercentEncoding {
+                    value: "%zz".to_string(),
+                },
+            ),
+        ];
+
+        for (uri, network, expected) in cases {
+            let error = PaymentUri::new(uri, network).unwrap_err();
+            assert_eq!(error.to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_payment_uri_extra_params() {
+        let payment_uri = PaymentUri::new(format!("bitcoin:{}", ADDRESS), Network::Testnet)
+            .unwrap()
+            .with_extra_param("pjos".to_string(), "0".to_string())
+            .unwrap();
+        assert_eq!(
+            payment_uri.to_string(),
+            format!("bitcoin:{}?pjos=0", ADDRESS)
+        );
+
+        assert!(matches!(
+            payment_uri.with_extra_param("amount".to_string(), "1".to_string()),
+            Err(PaymentUriError::ReservedParam { key }) if key == "amount"
+        ));
+        assert!(matches!(
+            payment_uri.with_extra_param("pj".to_string(), "https://example.com".to_string()),
+            Err(PaymentUriError::ReservedParam { key }) if key == "pj"
+        ));
+        assert!(matches!(
+            payment_uri.with_extra_param("req-somethingnew".to_string(), "1".to_string()),
+            Err(PaymentUriError::UnknownRequiredParam { key }) if key == "req-somethingnew"
+        ));
+    }
+}
@@ -0,0 +1,531 @@
+use crate::bitcoin::{Psbt, Transaction};
+use crate::error::QrError;
+
+use bdk_wallet::bitcoin::consensus::encode::deserialize;
+use bdk_wallet::bitcoin::Psbt as BdkPsbt;
+use bdk_wallet::bitcoin::Transaction as BdkTransaction;
+
+use bbqr::file_type::FileType;
+use bbqr::join::Joined;
+use bbqr::split::{Split, SplitOptions};
+
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryInto;
+use std::sync::{Arc, Mutex};
+
+const PSBT_MAGIC: [u8; 5] = [0x70, 0x73, 0x62, 0x74, 0xff];
+const BBQR_HEADER_LENGTH: usize = 8;
+
+#[derive(Clone, Debug)]
+pub enum QrEncoding {
+    Bbqr {
+        min_split_number: u32,
+        max_split_number: u32,
+    },
+    Ur {
+        max_fragment_length: u32,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum QrPayload {
+    Psbt,
+    Transaction,
+}
+
+impl QrPayload {
+    fn bbqr_file_type(&self) -> FileType {
+        match self {
+            QrPayload::Psbt => FileType::Psbt,
+            QrPayload::Transaction => FileType::Transaction,
+        }
+    }
+
+    fn ur_type(&self) -> &'static str {
+        match self {
+            QrPayload::Psbt => "crypto-psbt",
+            QrPayload::Transaction => "bytes",
+        }
+    }
+}
+
+/// Split a serialized PSBT or transaction into the frames of an animated QR code.
+pub(crate) fn encode_frames(
+    data: &[u8],
+    payload: QrPayload,
+    encoding: QrEncoding,
+) -> Result<Vec<String>, QrError> {
+    match encoding {
+        QrEncoding::Bbqr {
+            min_split_number,
+            max_split_number,
+        } => {
+            let options = SplitOptions {
+                min_split_number: min_split_number as usize,
+                max_split_number: max_split_number as usize,
+                ..SplitOptions::default()
+            };
+            let split =
+                Split::try_from_data(data, payload.bbqr_file_type(), options).map_err(|e| {
+                    QrError::Bbqr {
+                        error_message: e.to_string(),
+                    }
+                })?;
+            Ok(split.parts)
+        }
+        QrEncoding::Ur {
+            max_fragment_length,
+        } => {
+            let message = encode_cbor_bytes(data);
+            let mut encoder =
+                ur::Encoder::new(&message, max_fragment_length as usize, payload.ur_type())
+                    .map_err(|e| QrError::Ur {
+                        error_message: e.to_string(),
+                    })?;
+            (0..encoder.fragment_count())
+                .map(|_| {
+                    encoder.next_part().map_err(|e| QrError::Ur {
+                        error_message: e.to_string(),
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+pub struct QrDecodeProgress {
+    pub received_frames: u32,
+    pub expected_frames: u32,
+    pub is_complete: bool,
+}
+
+enum QrDecoderState {
+    Empty,
+    Bbqr {
+        header: BbqrHeader,
+        parts: BTreeMap<u32, String>,
+    },
+    Ur {
+        ur_type: String,
+        total: u32,
+        seen: HashSet<u32>,
+        decoder: ur::Decoder,
+    },
+    Complete {
+        payload: QrPayload,
+        data: Vec<u8>,
+        frames: u32,
+    },
+}
+
+/// Collects the frames of an animated BBQr or UR code, in any order, until the payload can be
+/// reassembled.
+pub struct QrDecoder(Mutex<QrDecoderState>);
+
+impl QrDecoder {
+    pub(crate) fn new() -> Self {
+        QrDecoder(Mutex::new(QrDecoderState::Empty))
+    }
+
+    pub fn receive(&self, frame: String) -> Result<QrDecodeProgress, QrError> {
+        let mut state = self.0.lock().unwrap();
+        let frame = frame.trim();
+
+        if let QrDecoderState::Empty = *state {
+            *state = if frame.starts_with("B$") {
+                QrDecoderState::Bbqr {
+                    header: parse_bbqr_header(frame)?,
+                    parts: BTreeMap::new(),
+                }
+            } else if frame.to_lowercase().starts_with("ur:") {
+                let (ur_type, sequence) = parse_ur_header(&frame.to_lowercase())?;
+                QrDecoderState::Ur {
+                    ur_type,
+                    total: sequence.map(|(_, total)| total).unwrap_or(1),
+                    seen: HashSet::new(),
+                    decoder: ur::Decoder::default(),
+                }
+            } else {
+                return Err(QrError::UnknownFormat);
+            };
+        }
+
+        let completed = match &mut *state {
+            QrDecoderState::Empty => unreachable!("state is initialized from the first frame"),
+            QrDecoderState::Complete { .. } => None,
+            QrDecoderState::Bbqr { header, parts } => {
+                if !frame.starts_with("B$") {
+                    return Err(QrError::MixedFormats);
+                }
+                let frame_header = parse_bbqr_header(frame)?;
+                if !frame_header.same_sequence(header) {
+                    return Err(QrError::MixedFormats);
+                }
+                parts.insert(frame_header.index, frame.to_string());
+
+                if parts.len() as u32 == header.total {
+                    let joined = Joined::try_from_parts(parts.values().cloned().collect())
+                        .map_err(|e| QrError::Bbqr {
+                            error_message: e.to_string(),
+                        })?;
+                    let payload = match joined.file_type {
+                        FileType::Psbt => QrPayload::Psbt,
+                        FileType::Transaction => QrPayload::Transaction,
+                        other => {
+                            return Err(QrError::UnexpectedType {
+                                payload_type: format!("{:?}", other),
+                            })
+                        }
+                    };
+                    Some((payload, joined.data, header.total))
+                } else {
+                    None
+                }
+            }
+            QrDecoderState::Ur {
+                ur_type,
+                total,
+                seen,
+                decoder,
+            } => {
+                let frame = frame.to_lowercase();
+                let (frame_type, sequence) = parse_ur_header(&frame)?;
+                if frame_type != *ur_type {
+                    return Err(QrError::MixedFormats);
+                }
+
+                let message = match sequence {
+                    None => Some(
+                        ur::decode(&frame)
+                            .map_err(|e| QrError::Ur {
+                                error_message: e.to_string(),
+                            })?
+                            .1,
+                    ),
+                    Some((index, _)) => {
+                        seen.insert(index);
+                        decoder.receive(&frame).map_err(|e| QrError::Ur {
+                            error_message: e.to_string(),
+                        })?;
+                        if decoder.complete() {
+                            decoder.message().map_err(|e| QrError::Ur {
+                                error_message: e.to_string(),
+                            })?
+                        } else {
+                            None
+                        }
+                    }
+                };
+
+                match message {
+                    Some(message) => {
+                        let data = decode_cbor_bytes(&message)?;
+                        let payload = match ur_type.as_str() {
+                            "crypto-psbt" | "psbt" => QrPayload::Psbt,
+                            "bytes" if data.starts_with(&PSBT_MAGIC) => QrPayload::Psbt,
+                            "bytes" => QrPayload::Transaction,
+                            other => {
+                                return Err(QrError::UnexpectedType {
+                                    payload_type: other.to_string(),
+                                })
+                            }
+                        };
+                        Some((payload, data, (seen.len() as u32).max(*total)))
+                    }
+                    None => None,
+                }
+            }
+        };
+
+        if let Some((payload, data, frames)) = completed {
+            *state = QrDecoderState::Complete {
+                payload,
+                data,
+                frames,
+            };
+        }
+
+        Ok(progress(&state))
+    }
+
+    pub fn progress(&self) -> QrDecodeProgress {
+        progress(&self.0.lock().unwrap())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(*self.0.lock().unwrap(), QrDecoderState::Complete { .. })
+    }
+
+    pub fn psbt(&self) -> Result<Arc<Psbt>, QrError> {
+        match &*self.0.lock().unwrap() {
+            QrDecoderState::Complete {
+                payload: QrPayload::Psbt,
+                data,
+                ..
+            } => {
+                let psbt = BdkPsbt::deserialize(data).map_err(|e| QrError::InvalidPayload {
+                    error_message: e.to_string(),
+                })?;
+                Ok(Arc::new(psbt.into()))
+            }
+            QrDecoderState::Complete { .. } => Err(QrError::UnexpectedType {
+                payload_type: "transaction".to_string(),
+            }),
+            _ => Err(QrError::Incomplete),
+        }
+    }
+
+    pub fn transaction(&self) -> Result<Arc<Transaction>, QrError> {
+        match &*self.0.lock().unwrap() {
+            QrDecoderState::Complete {
+                payload: QrPayload::Transaction,
+                data,
+                ..
+            } => {
+                let tx: BdkTransaction =
+                    deserialize(data).map_err(|e| QrError::InvalidPayload {
+                        error_message: e.to_string(),
+                    })?;
+                Ok(Arc::new(tx.into()))
+            }
+            QrDecoderState::Complete { .. } => Err(QrError::UnexpectedType {
+                payload_type: "psbt".to_string(),
+            }),
+            _ => Err(QrError::Incomplete),
+        }
+    }
+}
+
+fn progress(state: &QrDecoderState) -> QrDecodeProgress {
+    match state {
+        QrDecoderState::Empty => QrDecodeProgress {
+            received_frames: 0,
+            expected_frames: 0,
+            is_complete: false,
+        },
+        QrDecoderState::Bbqr { header, parts } => QrDecodeProgress {
+            received_frames: parts.len() as u32,
+            expected_frames: header.total,
+            is_complete: false,
+        },
+        // Fountain-coded UR frames past the sequence length carry mixed fragments, so a UR
+        // animation can need more frames than expected before it completes.
+        QrDecoderState::Ur { total, seen, .. } => QrDecodeProgress {
+            received_frames: seen.len() as u32,
+            expected_frames: *total,
+            is_complete: false,
+        },
+        QrDecoderState::Complete { frames, .. } => QrDecodeProgress {
+            received_frames: *frames,
+            expected_frames: *frames,
+            is_complete: true,
+        },
+    }
+}
+
+/// The `B$` header of a BBQr frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BbqrHeader {
+    encoding: char,
+    file_type: char,
+    total: u32,
+    index: u32,
+}
+
+impl BbqrHeader {
+    /// Whether both frames belong to the same animation.
+    fn same_sequence(&self, other: &BbqrHeader) -> bool {
+        self.encoding == other.encoding
+            && self.file_type == other.file_type
+            && self.total == other.total
+    }
+}
+
+/// Parse the `B$` header of a BBQr frame into its encoding, file type, total number of frames and
+/// this frame's index.
+fn parse_bbqr_header(frame: &str) -> Result<BbqrHeader, QrError> {
+    let header = frame
+        .get(..BBQR_HEADER_LENGTH)
+        .filter(|header| header.is_ascii())
+        .ok_or(QrError::InvalidHeader)?;
+    let mut chars = header.chars().skip(2);
+    let encoding = chars.next().ok_or(QrError::InvalidHeader)?;
+    let file_type = chars.next().ok_or(QrError::InvalidHeader)?;
+    let total = u32::from_str_radix(&header[4..6], 36).map_err(|_| QrError::InvalidHeader)?;
+    let index = u32::from_str_radix(&header[6..8], 36).map_err(|_| QrError::InvalidHeader)?;
+    if total == 0 || index >= total {
+        return Err(QrError::InvalidHeader);
+    }
+    Ok(BbqrHeader {
+        encoding,
+        file_type,
+        total,
+        index,
+    })
+}
+
+/// Parse a lowercase `ur:<type>[/<seq>-<len>]/<payload>` frame into its type and, for multi-part
+/// frames, the 1-based sequence number and sequence length.
+fn parse_ur_header(frame: &str) -> Result<(String, Option<(u32, u32)>), QrError> {
+    let components: Vec<&str> = frame
+        .strip_prefix("ur:")
+        .ok_or(QrError::InvalidHeader)?
+        .split('/')
+        .collect();
+    match components.as_slice() {
+        [ur_type, _] => Ok((ur_type.to_string(), None)),
+        [ur_type, sequence, _] => {
+            let (index, total) = sequence.split_once('-').ok_or(QrError::InvalidHeader)?;
+            let index = index.parse().map_err(|_| QrError::InvalidHeader)?;
+            let total = total.parse().map_err(|_| QrError::InvalidHeader)?;
+            Ok((ur_type.to_string(), Some((index, total))))
+        }
+        _ => Err(QrError::InvalidHeader),
+    }
+}
+
+/// Wrap raw bytes in a CBOR byte string, as required by the `crypto-psbt` and `bytes` UR types.
+fn encode_cbor_bytes(data: &[u8]) -> Vec<u8> {
+    let length = data.len();
+    let mut encoded = Vec::with_capacity(length + 9);
+    match length {
+        0..=23 => encoded.push(0x40 | length as u8),
+        24..=0xff => encoded.extend_from_slice(&[0x58, length as u8]),
+        0x100..=0xffff => {
+            encoded.push(0x59);
+            encoded.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        _ => {
+            encoded.push(0x5a);
+            encoded.extend_from_slice(&(length as u32).to_be_bytes());
+        }
+    }
+    encoded.extend_from_slice(data);
+    encoded
+}
+
+fn decode_cbor_bytes(message: &[u8]) -> Result<Vec<u8>, QrError> {
+    let invalid = || QrError::InvalidPayload {
+        error_message: "expected a cbor byte string".to_string(),
+    };
+    let (&initial, rest) = message.split_first().ok_or_else(invalid)?;
+    if initial >> 5 != 2 {
+        return Err(invalid());
+    }
+    let (length, header_length) = match initial & 0x1f {
+        length @ 0..=23 => (length as usize, 0),
+        24 => (*rest.first().ok_or_else(invalid)? as usize, 1),
+        25 => {
+            let bytes: [u8; 2] = rest.get(..2).ok_or_else(invalid)?.try_into().unwrap();
+            (u16::from_be_bytes(bytes) as usize, 2)
+        }
+        26 => {
+            let bytes: [u8; 4] = rest.get(..4).ok_or_else(invalid)?.try_into().unwrap();
+            (u32::from_be_bytes(bytes) as usize, 4)
+        }
+        _ => return Err(invalid()),
+    };
+    rest.get(header_length..header_length + length)
+        .map(|data| data.to_vec())
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitcoin::Psbt;
+    use crate::error::QrError;
+    use crate::qr::{
+        decode_cbor_bytes, encode_cbor_bytes, parse_bbqr_header, QrDecoder, QrEncoding,
+    };
+
+    const PSBT: &str = "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA";
+
+    #[test]
+    fn test_cbor_bytes_round_trip() {
+        for length in [0usize, 23, 24, 255, 256, 70_000] {
+            let data = vec![0xab; length];
+            assert_eq!(decode_cbor_bytes(&encode_cbor_bytes(&data)).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_psbt_qr_round_trip() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let encodings = vec![
+            QrEncoding::Bbqr {
+                min_split_number: 3,
+                max_split_number: 10,
+            },
+            QrEncoding::Ur {
+                max_fragment_length: 60,
+            },
+        ];
+
+        for encoding in encodings {
+            let frames = psbt.to_qr_frames(encoding).unwrap();
+            assert!(frames.len() > 1);
+
+            let decoder = QrDecoder::new();
+            for frame in frames.iter().rev() {
+                decoder.receive(frame.clone()).unwrap();
+            }
+            assert!(decoder.is_complete());
+            assert_eq!(decoder.psbt().unwrap().serialize(), psbt.serialize());
+            assert!(decoder.transaction().is_err());
+        }
+    }
+
+    #[test]
+    fn test_bbqr_header() {
+        let header = parse_bbqr_header("B$ZP0301rest").unwrap();
+        assert_eq!((header.encoding, header.file_type), ('Z', 'P'));
+        assert_eq!((header.total, header.index), (3, 1));
+
+        assert!(matches!(
+            parse_bbqr_header("B$ZP03"),
+            Err(QrError::InvalidHeader)
+        ));
+        assert!(matches!(
+            parse_bbqr_header("B$ZP0303"),
+            Err(QrError::InvalidHeader)
+        ));
+        // A multibyte character across the total and index fields must not panic.
+        assert!(matches!(
+            parse_bbqr_header("B$ZP0\u{e9}1"),
+            Err(QrError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_bbqr_mixed_sequences() {
+        let psbt = Psbt::new(PSBT.to_string()).unwrap();
+        let frames = psbt
+            .to_qr_frames(QrEncoding::Bbqr {
+                min_split_number: 3,
+                max_split_number: 3,
+            })
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        let encoding = &frames[1][2..3];
+
+        let decoder = QrDecoder::new();
+        decoder.receive(frames[0].clone()).unwrap();
+        // Same number of frames, but a transaction instead of a PSBT.
+        let other_type = format!("B${}T{}", encoding, &frames[1][4..]);
+        assert!(matches!(
+            decoder.receive(other_type),
+            Err(QrError::MixedFormats)
+        ));
+        // Same number of frames and file type, but a different encoding.
+        let other_encoding = format!(
+            "B${}P{}",
+            if encoding == "H" { "Z" } else { "H" },
+            &frames[1][4..]
+        );
+        assert!(matches!(
+            decoder.receive(other_encoding),
+            Err(QrError::MixedFormats)
+        ));
+        assert_eq!(decoder.progress().received_frames, 1);
+    }
+}
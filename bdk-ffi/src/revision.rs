@@ -0,0 +1,430 @@
+use crate::bitcoin::Address;
+use crate::types::{LabelType, WalletChanges};
+
+use bitcoin_ffi::OutPoint;
+
+use bdk_wallet::bitcoin::address::NetworkUnchecked;
+use bdk_wallet::bitcoin::hashes::Hash;
+use bdk_wallet::bitcoin::Address as BdkAddress;
+use bdk_wallet::bitcoin::OutPoint as BdkOutPoint;
+use bdk_wallet::bitcoin::{BlockHash, Network, ScriptBuf, Txid};
+use bdk_wallet::chain::{BlockId, ConfirmationBlockTime, Merge};
+use bdk_wallet::rusqlite::{params, Connection, Error, OptionalExtension};
+use bdk_wallet::{ChangeSet, KeychainKind, Wallet as BdkWallet};
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const KEYCHAINS: [KeychainKind; 2] = [KeychainKind::External, KeychainKind::Internal];
+
+const CREATE_EPOCH_TABLE: &str = "CREATE TABLE IF NOT EXISTS bdkffi_revision_epoch (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    epoch INTEGER NOT NULL
+)";
+
+/// Starts the next revision epoch of the wallet stored in `db`, beginning at 1. Every time a
+/// wallet is created or loaded it gets a new epoch, so revisions handed out by earlier instances
+/// can be told apart from current ones.
+pub(crate) fn next_epoch(db: &Connection) -> Result<u32, Error> {
+    db.execute(CREATE_EPOCH_TABLE, [])?;
+    let epoch: Option<u32> = db
+        .query_row(
+            "SELECT epoch FROM bdkffi_revision_epoch WHERE id = 0",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let epoch = epoch.map_or(1, |epoch| epoch + 1);
+    db.execute(
+        "INSERT OR REPLACE INTO bdkffi_revision_epoch (id, epoch) VALUES (0, ?1)",
+        params![epoch],
+    )?;
+    Ok(epoch)
+}
+
+/// Records the revision at which each transaction, output and address last changed.
+///
+/// Changes are read from the `ChangeSet` each operation stages on the wallet. The log takes that
+/// `ChangeSet` and holds on to it until `Wallet::persist` writes it, so that the next operation
+/// stages only its own changes.
+pub(crate) struct RevisionLog {
+    /// Revisions of this wallet instance start at its epoch in the upper 32 bits.
+    first_revision: u64,
+    revision: u64,
+    txids: HashMap<Txid, u64>,
+    outpoints: HashMap<BdkOutPoint, u64>,
+    scripts: HashMap<ScriptBuf, u64>,
+    derivation_indices: [Option<u32>; 2],
+    unpersisted: ChangeSet,
+}
+
+impl RevisionLog {
+    pub(crate) fn new(wallet: &BdkWallet, epoch: u32) -> Self {
+        let first_revision = u64::from(epoch) << 32;
+        RevisionLog {
+            first_revision,
+            revision: first_revision,
+            txids: HashMap::new(),
+            outpoints: HashMap::new(),
+            scripts: HashMap::new(),
+            derivation_indices: KEYCHAINS.map(|keychain| wallet.derivation_index(keychain)),
+            unpersisted: ChangeSet::default(),
+        }
+    }
+
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Takes the changes staged on `wallet` since the last call and records what they touched.
+    pub(crate) fn record_staged(&mut self, wallet: &mut BdkWallet) {
+        let changeset = match wallet.take_staged() {
+            Some(changeset) => changeset,
+            None => return,
+        };
+        let graph = wallet.tx_graph();
+        let tx_changes = &changeset.tx_graph;
+
+        let mut txids: HashSet<Txid> = tx_changes
+            .txs
+            .iter()
+            .map(|tx| tx.compute_txid())
+            .chain(tx_changes.anchors.iter().map(|(_, txid)| *txid))
+            .chain(tx_changes.last_seen.keys().copied())
+            .collect();
+        // Transactions anchored in blocks that were added, replaced or removed may have moved in
+        // or out of the best chain.
+        for height in changeset.local_chain.blocks.keys() {
+            txids.extend(anchored_at(wallet, *height));
+        }
+        // Transactions evicted by a new one, and their descendants, leave the canonical history.
+        let evicted: Vec<Txid> = txids
+            .iter()
+            .filter_map(|txid| graph.get_tx(*txid))
+            .flat_map(|tx| {
+                graph
+                    .walk_conflicts(&tx, |_, txid| Some(txid))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        txids.extend(evicted);
+
+        // Wallet outputs created or spent by any of those transactions.
+        let spk_index = wallet.spk_index();
+        let mut outpoints = Vec::new();
+        let mut scripts = Vec::new();
+        for tx in txids.iter().filter_map(|txid| graph.get_tx(*txid)) {
+            let txid = tx.compute_txid();
+            let created = (0..tx.output.len() as u32).map(|vout| BdkOutPoint::new(txid, vout));
+            let spent = tx.input.iter().map(|input| input.previous_output);
+            for outpoint in created.chain(spent) {
+                if let Some((_, txout)) = spk_index.txout(outpoint) {
+                    outpoints.push(outpoint);
+                    scripts.push(txout.script_pubkey.clone());
+                }
+            }
+        }
+
+        if !changeset.indexer.last_revealed.is_empty() {
+            for (keychain, last_index) in KEYCHAINS.iter().zip(&mut self.derivation_indices) {
+                let new_index = wallet.derivation_index(*keychain);
+                let first_new = last_index.map_or(0, |index| index + 1);
+                if let Some(last_new) = new_index {
+                    scripts.extend(
+                        (first_new..=last_new)
+                            .map(|index| wallet.peek_address(*keychain, index).script_pubkey()),
+                    );
+                }
+                *last_index = new_index;
+            }
+        }
+
+        self.record(txids.into_iter().collect(), outpoints, scripts);
+        self.unpersisted.merge(changeset);
+    }
+
+    /// Records the wallet data a label is attached to. Public key and xpub labels change nothing
+    /// the wallet lists.
+    pub(crate) fn record_labels<'a>(
+        &mut self,
+        labels: impl IntoIterator<Item = (LabelType, &'a str)>,
+    ) {
+        let mut txids = Vec::new();
+        let mut outpoints = Vec::new();
+        let mut scripts = Vec::new();
+        for (label_type, reference) in labels {
+            match label_type {
+                LabelType::Tx => txids.extend(Txid::from_str(reference).ok()),
+                LabelType::Output => outpoints.extend(BdkOutPoint::from_str(reference).ok()),
+                // Inputs are referenced as `txid:vin` of the spending transaction.
+                LabelType::Input => txids.extend(
+                    BdkOutPoint::from_str(reference)
+                        .ok()
+                        .map(|input| input.txid),
+                ),
+                LabelType::Address => scripts.extend(
+                    BdkAddress::<NetworkUnchecked>::from_str(reference)
+                        .ok()
+                        .map(|address| address.assume_checked().script_pubkey()),
+                ),
+                LabelType::Pubkey | LabelType::Xpub => {}
+            }
+        }
+        self.record(txids, outpoints, scripts);
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        txids: Vec<Txid>,
+        outpoints: Vec<BdkOutPoint>,
+        scripts: Vec<ScriptBuf>,
+    ) {
+        if txids.is_empty() && outpoints.is_empty() && scripts.is_empty() {
+            return;
+        }
+
+        self.revision += 1;
+        let revision = self.revision;
+        self.txids
+            .extend(txids.into_iter().map(|txid| (txid, revision)));
+        self.outpoints
+            .extend(outpoints.into_iter().map(|outpoint| (outpoint, revision)));
+        self.scripts
+            .extend(scripts.into_iter().map(|script| (script, revision)));
+    }
+
+    /// Everything staged on the wallet that was not persisted yet.
+    pub(crate) fn take_unpersisted(&mut self, wallet: &mut BdkWallet) -> ChangeSet {
+        self.record_staged(wallet);
+        mem::take(&mut self.unpersisted)
+    }
+
+    /// Hands back changes taken by `take_unpersisted` that could not be written.
+    pub(crate) fn restore_unpersisted(&mut self, changeset: ChangeSet) {
+        let newer = mem::replace(&mut self.unpersisted, changeset);
+        self.unpersisted.merge(newer);
+    }
+
+    pub(crate) fn changes_since(&self, revision: u64, network: Network) -> WalletChanges {
+        // Revisions from an earlier instance of the wallet, or never handed out, say nothing
+        // about what the caller has seen.
+        if revision < self.first_revision || revision > self.revision {
+            return WalletChanges {
+                revision: self.revision,
+                full_resync: true,
+                txids: Vec::new(),
+                outpoints: Vec::new(),
+                addresses: Vec::new(),
+            };
+        }
+
+        WalletChanges {
+            revision: self.revision,
+            full_resync: false,
+            txids: changed_since(&self.txids, revision),
+            outpoints: changed_since(&self.outpoints, revision)
+                .into_iter()
+                .map(|outpoint| OutPoint {
+                    txid: outpoint.txid,
+                    vout: outpoint.vout,
+                })
+                .collect(),
+            addresses: changed_since(&self.scripts, revision)
+                .into_iter()
+                .filter_map(|script| BdkAddress::from_script(&script, network).ok())
+                .map(|address| Arc::new(Address::from(address)))
+                .collect(),
+        }
+    }
+}
+
+/// Transactions with an anchor in a block at `height`, whichever block that was.
+fn anchored_at(wallet: &BdkWallet, height: u32) -> impl Iterator<Item = Txid> + '_ {
+    let lowest_anchor = |height| {
+        let block_id = BlockId {
+            height,
+            hash: BlockHash::all_zeros(),
+        };
+        let anchor = ConfirmationBlockTime {
+            block_id,
+            confirmation_time: 0,
+        };
+        (anchor, Txid::all_zeros())
+    };
+    let anchors = wallet.tx_graph().all_anchors();
+    let anchors = match height.checked_add(1) {
+        Some(next_height) => anchors.range(lowest_anchor(height)..lowest_anchor(next_height)),
+        None => anchors.range(lowest_anchor(height)..),
+    };
+    anchors.map(|(_, txid)| *txid)
+}
+
+fn changed_since<K: Clone>(revisions: &HashMap<K, u64>, revision: u64) -> Vec<K> {
+    revisions
+        .iter()
+        .filter(|(_, changed_at)| **changed_at > revision)
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::revision::{next_epoch, RevisionLog};
+    use crate::test_utils::{
+        block_id, confirmed, foreign_outpoint, new_tx, new_wallet, unconfirmed,
+    };
+    use crate::types::LabelType;
+
+    use bdk_wallet::bitcoin::hashes::Hash;
+    use bdk_wallet::bitcoin::{BlockHash, OutPoint, ScriptBuf, Txid};
+    use bdk_wallet::chain::{BlockId, CheckPoint};
+    use bdk_wallet::rusqlite::Connection;
+    use bdk_wallet::{KeychainKind, Update};
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_revision_epochs() {
+        let db = Connection::open_in_memory().unwrap();
+        assert_eq!(next_epoch(&db).unwrap(), 1);
+        assert_eq!(next_epoch(&db).unwrap(), 2);
+
+        let wallet = new_wallet();
+        let log = RevisionLog::new(&wallet, 2);
+        assert_eq!(log.revision(), 2 << 32);
+        // Revisions from the previous epoch and from the future need a full resync.
+        let network = wallet.network();
+        assert!(!log.changes_since(log.revision(), network).full_resync);
+        assert!(log.changes_since(0, network).full_resync);
+        assert!(log.changes_since((1 << 32) + 5, network).full_resync);
+        assert!(log.changes_since(log.revision() + 1, network).full_resync);
+    }
+
+    #[test]
+    fn test_revision_changes() {
+        let mut wallet = new_wallet();
+        let mut log = RevisionLog::new(&wallet, 1);
+        let network = wallet.network();
+        let txids_since = |log: &RevisionLog, revision| -> HashSet<Txid> {
+            log.changes_since(revision, network)
+                .txids
+                .into_iter()
+                .collect()
+        };
+        let outpoints_since = |log: &RevisionLog, revision| -> HashSet<OutPoint> {
+            log.changes_since(revision, network)
+                .outpoints
+                .into_iter()
+                .map(|outpoint| OutPoint::new(outpoint.txid, outpoint.vout))
+                .collect()
+        };
+
+        let start = log.revision();
+        let address = wallet.reveal_next_address(KeychainKind::External);
+        log.record_staged(&mut wallet);
+        let changes = log.changes_since(start, network);
+        assert_eq!(changes.revision, start + 1);
+        assert_eq!(changes.addresses.len(), 1);
+        assert_eq!(
+            changes.addresses[0].script_pubkey().0,
+            address.script_pubkey()
+        );
+        // Nothing staged, nothing recorded.
+        log.record_staged(&mut wallet);
+        assert_eq!(log.revision(), start + 1);
+
+        let funding = new_tx(
+            &[foreign_outpoint(1)],
+            vec![(address.script_pubkey(), 50_000)],
+        );
+        let funding_outpoint = OutPoint::new(funding.compute_txid(), 0);
+        let update = confirmed(&wallet, &[&funding], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+        log.record_staged(&mut wallet);
+        assert_eq!(
+            txids_since(&log, start + 1),
+            HashSet::from([funding.compute_txid()])
+        );
+        assert_eq!(
+            outpoints_since(&log, start + 1),
+            HashSet::from([funding_outpoint])
+        );
+
+        // A spend changes the output it spends, and the change address it reveals.
+        let funded = log.revision();
+        let change = wallet.reveal_next_address(KeychainKind::Internal);
+        let spend = new_tx(
+            &[funding_outpoint],
+            vec![(ScriptBuf::new(), 30_000), (change.script_pubkey(), 19_000)],
+        );
+        wallet
+            .apply_update_at(unconfirmed(&[&spend], 2_000), None)
+            .unwrap();
+        log.record_staged(&mut wallet);
+        let spend_change = OutPoint::new(spend.compute_txid(), 1);
+        assert_eq!(
+            txids_since(&log, funded),
+            HashSet::from([spend.compute_txid()])
+        );
+        assert_eq!(
+            outpoints_since(&log, funded),
+            HashSet::from([funding_outpoint, spend_change])
+        );
+        assert_eq!(log.changes_since(funded, network).addresses.len(), 2);
+
+        // The replacement evicts the first spend, whose change output disappears.
+        let spent = log.revision();
+        let replacement = new_tx(&[funding_outpoint], vec![(ScriptBuf::new(), 49_000)]);
+        wallet
+            .apply_update_at(unconfirmed(&[&replacement], 3_000), None)
+            .unwrap();
+        log.record_staged(&mut wallet);
+        assert_eq!(
+            txids_since(&log, spent),
+            HashSet::from([replacement.compute_txid(), spend.compute_txid()])
+        );
+        assert!(outpoints_since(&log, spent).contains(&spend_change));
+
+        // A reorg replacing the funding block unconfirms the funding transaction.
+        let replaced = log.revision();
+        let genesis = wallet.local_chain().genesis_hash();
+        let chain = CheckPoint::new(BlockId {
+            height: 0,
+            hash: genesis,
+        })
+        .push(BlockId {
+            height: 1,
+            hash: BlockHash::all_zeros(),
+        })
+        .unwrap();
+        assert_ne!(block_id(1).hash, BlockHash::all_zeros());
+        let update = Update {
+            chain: Some(chain),
+            ..Update::default()
+        };
+        wallet.apply_update_at(update, None).unwrap();
+        log.record_staged(&mut wallet);
+        assert!(txids_since(&log, replaced).contains(&funding.compute_txid()));
+
+        // Labels record what they are attached to.
+        let labelled = log.revision();
+        log.record_labels([
+            (LabelType::Output, funding_outpoint.to_string().as_str()),
+            (LabelType::Xpub, "xpub"),
+        ]);
+        assert_eq!(
+            outpoints_since(&log, labelled),
+            HashSet::from([funding_outpoint])
+        );
+
+        // Every staged change is kept for persisting.
+        let unpersisted = log.take_unpersisted(&mut wallet);
+        assert_eq!(unpersisted.tx_graph.txs.len(), 3);
+        assert!(!unpersisted.indexer.last_revealed.is_empty());
+        assert!(log.take_unpersisted(&mut wallet).tx_graph.txs.is_empty());
+        log.restore_unpersisted(unpersisted);
+        assert_eq!(log.take_unpersisted(&mut wallet).tx_graph.txs.len(), 3);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::error::ScriptError;
+
+use bitcoin_ffi::Script;
+
+use bdk_wallet::bitcoin::opcodes::all::{OP_PUSHDATA4, OP_RETURN};
+use bdk_wallet::bitcoin::opcodes::Opcode;
+use bdk_wallet::bitcoin::script::{Builder as BdkScriptBuilder, Instruction, PushBytesBuf};
+use bdk_wallet::bitcoin::Script as BdkScript;
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+// Consensus limit on the size of a single stack element.
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScriptType {
+    P2pk,
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    OpReturn,
+    Multisig,
+    UnknownWitness { version: u8 },
+    NonStandard,
+}
+
+impl ScriptType {
+    // Script type names as reported by Bitcoin Core.
+    pub(crate) fn core_name(&self) -> &'static str {
+        match self {
+            ScriptType::P2pk => "pubkey",
+            ScriptType::P2pkh => "pubkeyhash",
+            ScriptType::P2sh => "scripthash",
+            ScriptType::P2wpkh => "witness_v0_keyhash",
+            ScriptType::P2wsh => "witness_v0_scripthash",
+            ScriptType::P2tr => "witness_v1_taproot",
+            ScriptType::OpReturn => "nulldata",
+            ScriptType::Multisig => "multisig",
+            ScriptType::UnknownWitness { .. } => "witness_unknown",
+            ScriptType::NonStandard => "nonstandard",
+        }
+    }
+}
+
+impl From<&BdkScript> for ScriptType {
+    fn from(script: &BdkScript) -> Self {
+        if script.is_p2pkh() {
+            ScriptType::P2pkh
+        } else if script.is_p2sh() {
+            ScriptType::P2sh
+        } else if script.is_p2wpkh() {
+            ScriptType::P2wpkh
+        } else if script.is_p2wsh() {
+            ScriptType::P2wsh
+        } else if script.is_p2tr() {
+            ScriptType::P2tr
+        } else if script.is_op_return() {
+            ScriptType::OpReturn
+        } else if script.is_p2pk() {
+            ScriptType::P2pk
+        } else if script.is_multisig() {
+            ScriptType::Multisig
+        } else if let Some(version) = script.witness_version() {
+            ScriptType::UnknownWitness {
+                version: version.to_num(),
+            }
+        } else {
+            ScriptType::NonStandard
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScriptInstruction {
+    PushBytes { data: Vec<u8> },
+    Op { opcode: u8, name: String },
+}
+
+impl From<Instruction<'_>> for ScriptInstruction {
+    fn from(instruction: Instruction<'_>) -> Self {
+        match instruction {
+            Instruction::PushBytes(bytes) => ScriptInstruction::PushBytes {
+                data: bytes.as_bytes().to_vec(),
+            },
+            Instruction::Op(opcode) => ScriptInstruction::Op {
+                opcode: opcode.to_u8(),
+                name: opcode.to_string(),
+            },
+        }
+    }
+}
+
+/// Read-only view over a `Script` for disassembly and classification.
+pub struct ScriptInfo(Arc<Script>);
+
+impl ScriptInfo {
+    pub fn new(script: Arc<Script>) -> Self {
+        ScriptInfo(script)
+    }
+
+    pub fn asm(&self) -> String {
+        self.0 .0.to_asm_string()
+    }
+
+    pub fn instructions(&self) -> Result<Vec<ScriptInstruction>, ScriptError> {
+        self.0
+             .0
+            .instructions()
+            .map(|instruction| instruction.map(ScriptInstruction::from))
+            .collect::<Result<_, _>>()
+            .map_err(ScriptError::from)
+    }
+
+    pub fn script_type(&self) -> ScriptType {
+        ScriptType::from(self.0 .0.as_script())
+    }
+
+    pub fn is_push_only(&self) -> bool {
+        self.0 .0.is_push_only()
+    }
+
+    /// The data pushed after `OP_RETURN`, or `None` if this is not a well-formed null data
+    /// script.
+    pub fn op_return_payload(&self) -> Option<Vec<u8>> {
+        let mut instructions = self.0 .0.instructions();
+        match instructions.next() {
+            Some(Ok(Instruction::Op(opcode))) if opcode == OP_RETURN => {}
+            _ => return None,
+        }
+
+        let mut payload = Vec::new();
+        for instruction in instructions {
+            match instruction {
+                Ok(Instruction::PushBytes(bytes)) => payload.extend_from_slice(bytes.as_bytes()),
+                _ => return None,
+            }
+        }
+        Some(payload)
+    }
+}
+
+#[derive(Clone)]
+pub struct ScriptBuilder(BdkScriptBuilder);
+
+impl ScriptBuilder {
+    pub(crate) fn new() -> Self {
+        ScriptBuilder(BdkScriptBuilder::new())
+    }
+
+    /// Pushes a single opcode. Data push opcodes are rejected, use `push_slice` instead.
+    pub fn push_opcode(&self, opcode: u8) -> Result<Arc<Self>, ScriptError> {
+        if (0x01..=OP_PUSHDATA4.to_u8()).contains(&opcode) {
+            return Err(ScriptError::InvalidOpcode { opcode });
+        }
+        Ok(Arc::new(ScriptBuilder(
+            self.0.clone().push_opcode(Opcode::from(opcode)),
+        )))
+    }
+
+    /// Pushes data using the smallest possible push opcode.
+    pub fn push_slice(&self, data: Vec<u8>) -> Result<Arc<Self>, ScriptError> {
+        let length = data.len() as u64;
+        if data.len() > MAX_SCRIPT_ELEMENT_SIZE {
+            return Err(ScriptError::PushTooLarge { length });
+        }
+        let data =
+            PushBytesBuf::try_from(data).map_err(|_| ScriptError::PushTooLarge { length })?;
+        Ok(Arc::new(ScriptBuilder(self.0.clone().push_slice(data))))
+    }
+
+    /// Pushes a number using `OP_0`-`OP_16` where possible and a minimal encoding otherwise.
+    pub fn push_int(&self, value: i64) -> Arc<Self> {
+        Arc::new(ScriptBuilder(self.0.clone().push_int(value)))
+    }
+
+    pub fn build(&self) -> Arc<Script> {
+        Arc::new(Script(self.0.clone().into_script()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ScriptError;
+    use crate::script::{ScriptBuilder, ScriptInfo, ScriptInstruction, ScriptType};
+
+    use bitcoin_ffi::Script;
+
+    use bdk_wallet::bitcoin::ScriptBuf;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_script_builder_and_info() {
+        let hash = vec![0xab; 20];
+        // OP_IF OP_HASH160 <hash> OP_EQUALVERIFY OP_ELSE <144> OP_CSV OP_DROP OP_ENDIF OP_TRUE
+        let script = ScriptBuilder::new()
+            .push_opcode(0x63)
+            .unwrap()
+            .push_opcode(0xa9)
+            .unwrap()
+            .push_slice(hash.clone())
+            .unwrap()
+            .push_opcode(0x88)
+            .unwrap()
+            .push_opcode(0x67)
+            .unwrap()
+            .push_int(144)
+            .push_opcode(0xb2)
+            .unwrap()
+            .push_opcode(0x75)
+            .unwrap()
+            .push_opcode(0x68)
+            .unwrap()
+            .push_int(1)
+            .build();
+
+        let info = ScriptInfo::new(script);
+        assert_eq!(info.script_type(), ScriptType::NonStandard);
+        assert!(!info.is_push_only());
+        assert_eq!(info.op_return_payload(), None);
+
+        let instructions = info.instructions().unwrap();
+        assert_eq!(instructions.len(), 10);
+        assert_eq!(
+            instructions[1],
+            ScriptInstruction::Op {
+                opcode: 0xa9,
+                name: "OP_HASH160".to_string()
+            }
+        );
+        assert_eq!(instructions[2], ScriptInstruction::PushBytes { data: hash });
+        assert_eq!(
+            instructions[9],
+            ScriptInstruction::Op {
+                opcode: 0x51,
+                name: "OP_PUSHNUM_1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_script_op_return() {
+        let script = ScriptBuilder::new()
+            .push_opcode(0x6a)
+            .unwrap()
+            .push_slice(b"hello".to_vec())
+            .unwrap()
+            .build();
+
+        let info = ScriptInfo::new(script);
+        assert_eq!(info.script_type(), ScriptType::OpReturn);
+        assert_eq!(info.script_type().core_name(), "nulldata");
+        assert_eq!(info.asm(), "OP_RETURN OP_PUSHBYTES_5 68656c6c6f");
+        assert_eq!(info.op_return_payload(), Some(b"hello".to_vec()));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        let builder = ScriptBuilder::new();
+        assert!(matches!(
+            builder.push_opcode(0x4c),
+            Err(ScriptError::InvalidOpcode { opcode: 0x4c })
+        ));
+        assert!(matches!(
+            builder.push_slice(vec![0; 521]),
+            Err(ScriptError::PushTooLarge { length: 521 })
+        ));
+
+        // OP_PUSHBYTES_20 followed by only two bytes.
+        let truncated = Arc::new(Script(ScriptBuf::from_bytes(vec![0x14, 0x01, 0x02])));
+        assert!(matches!(
+            ScriptInfo::new(truncated).instructions(),
+            Err(ScriptError::Malformed { .. })
+        ));
+    }
+}
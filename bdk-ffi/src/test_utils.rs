@@ -0,0 +1,111 @@
+//! Wallets and chain data for unit tests that need transactions in a wallet.
+
+use crate::descriptor::Descriptor;
+use crate::store::Connection;
+
+use bdk_wallet::bitcoin::absolute::LockTime;
+use bdk_wallet::bitcoin::hashes::Hash;
+use bdk_wallet::bitcoin::transaction::Version;
+use bdk_wallet::bitcoin::{
+    Amount, BlockHash, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    Witness,
+};
+use bdk_wallet::chain::{BlockId, ConfirmationBlockTime, TxUpdate};
+use bdk_wallet::{Update, Wallet};
+
+use std::sync::Arc;
+
+pub(crate) const DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPdWuqM1t1CDRvQtQuBPyfL6GbhQwtxDKgUAVPbxmj71pRA8raTqLrec5LyTs5TqCxdABcZr77bt2KyWA5bizJHnC4g4ysm4h/84h/1h/0h/0/*)";
+pub(crate) const CHANGE_DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPdWuqM1t1CDRvQtQuBPyfL6GbhQwtxDKgUAVPbxmj71pRA8raTqLrec5LyTs5TqCxdABcZr77bt2KyWA5bizJHnC4g4ysm4h/84h/1h/0h/1/*)";
+
+/// A binding wallet stored in an in-memory database.
+pub(crate) fn new_persisted_wallet() -> Arc<crate::wallet::Wallet> {
+    let descriptor = Descriptor::new(DESCRIPTOR.to_string(), Network::Regtest).unwrap();
+    let change_descriptor =
+        Descriptor::new(CHANGE_DESCRIPTOR.to_string(), Network::Regtest).unwrap();
+    let connection = Connection::new_in_memory().unwrap();
+    let wallet = crate::wallet::Wallet::new(
+        Arc::new(descriptor),
+        Arc::new(change_descriptor),
+        Network::Regtest,
+        Arc::new(connection),
+    );
+    Arc::new(wallet.unwrap())
+}
+
+pub(crate) fn new_wallet() -> Wallet {
+    Wallet::create(DESCRIPTOR, CHANGE_DESCRIPTOR)
+        .network(Network::Regtest)
+        .create_wallet_no_persist()
+        .unwrap()
+}
+
+/// An output of a transaction the wallet knows nothing about, distinct for every `seed`.
+pub(crate) fn foreign_outpoint(seed: u8) -> OutPoint {
+    OutPoint::new(Txid::from_byte_array([seed; 32]), 0)
+}
+
+/// A version 2 transaction signalling RBF. Inputs have no signatures, which the wallet never
+/// checks.
+pub(crate) fn new_tx(inputs: &[OutPoint], outputs: Vec<(ScriptBuf, u64)>) -> Transaction {
+    Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: inputs
+            .iter()
+            .map(|outpoint| TxIn {
+                previous_output: *outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: outputs
+            .into_iter()
+            .map(|(script_pubkey, value)| TxOut {
+                value: Amount::from_sat(value),
+                script_pubkey,
+            })
+            .collect(),
+    }
+}
+
+pub(crate) fn block_id(height: u32) -> BlockId {
+    BlockId {
+        height,
+        hash: BlockHash::hash(&height.to_be_bytes()),
+    }
+}
+
+/// Confirms `txs` in a block at `height`, which is added to the wallet's chain.
+pub(crate) fn confirmed(wallet: &Wallet, txs: &[&Transaction], height: u32, time: u64) -> Update {
+    let block_id = block_id(height);
+    let mut tx_update = TxUpdate::default();
+    for tx in txs {
+        tx_update.txs.push(Arc::new((*tx).clone()));
+        let anchor = ConfirmationBlockTime {
+            block_id,
+            confirmation_time: time,
+        };
+        tx_update.anchors.insert((anchor, tx.compute_txid()));
+    }
+    Update {
+        tx_update,
+        chain: Some(wallet.latest_checkpoint().insert(block_id)),
+        ..Update::default()
+    }
+}
+
+/// Adds `txs` to the wallet as last seen in the mempool at `time`. Apply it with
+/// `apply_update_at(update, None)` to keep that time, as `apply_update` also marks them seen now.
+pub(crate) fn unconfirmed(txs: &[&Transaction], time: u64) -> Update {
+    let mut tx_update = TxUpdate::default();
+    for tx in txs {
+        tx_update.txs.push(Arc::new((*tx).clone()));
+        tx_update.seen_ats.insert(tx.compute_txid(), time);
+    }
+    Update {
+        tx_update,
+        ..Update::default()
+    }
+}
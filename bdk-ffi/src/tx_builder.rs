@@ -12,7 +12,6 @@ use bdk_wallet::bitcoin::ScriptBuf as BdkScriptBuf;
 use bdk_wallet::ChangeSpendPolicy;
 
 use std::collections::HashSet;
-use std::str::FromStr;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -177,9 +176,19 @@ impl TxBuilder {
     }
 
     pub(crate) fn finish(&self, wallet: &Arc<Wallet>) -> Result<Arc<Psbt>, CreateTxError> {
+        let frozen = wallet.frozen_outpoints();
+        if let Some(outpoint) = self
+            .utxos
+            .iter()
+            .find(|outpoint| frozen.contains(*outpoint))
+        {
+            return Err(CreateTxError::FrozenUtxo {
+                outpoint: outpoint.to_string(),
+            });
+        }
         // TODO: I had to change the wallet here to be mutable. Why is that now required with the 1.0 API?
-        let mut wallet = wallet.get_wallet();
-        let mut tx_builder = wallet.build_tx();
+        let mut bdk_wallet = wallet.get_wallet();
+        let mut tx_builder = bdk_wallet.build_tx();
         if self.add_global_xpubs {
             tx_builder.add_global_xpubs();
         }
@@ -192,8 +201,8 @@ impl TxBuilder {
                 .add_utxos(&self.utxos)
                 .map_err(CreateTxError::from)?;
         }
-        if !self.unspendable.is_empty() {
-            let bdk_unspendable: Vec<OutPoint> = self.unspendable.clone().into_iter().collect();
+        if !self.unspendable.is_empty() || !frozen.is_empty() {
+            let bdk_unspendable: Vec<OutPoint> = self.unspendable.union(&frozen).copied().collect();
             tx_builder.unspendable(bdk_unspendable);
         }
         if self.manually_selected_only {
@@ -222,7 +231,10 @@ impl TxBuilder {
             }
         }
 
-        let psbt = tx_builder.finish().map_err(CreateTxError::from)?;
+        let psbt = tx_builder.finish();
+        // Building reveals a change address even when it fails afterwards.
+        wallet.record_staged(&mut bdk_wallet);
+        let psbt = psbt.map_err(CreateTxError::from)?;
 
         Ok(Arc::new(psbt.into()))
     }
@@ -230,13 +242,13 @@ impl TxBuilder {
 
 #[derive(Clone)]
 pub(crate) struct BumpFeeTxBuilder {
-    pub(crate) txid: String,
+    pub(crate) txid: Txid,
     pub(crate) fee_rate: Arc<FeeRate>,
     pub(crate) rbf: Option<RbfValue>,
 }
 
 impl BumpFeeTxBuilder {
-    pub(crate) fn new(txid: String, fee_rate: Arc<FeeRate>) -> Self {
+    pub(crate) fn new(txid: Txid, fee_rate: Arc<FeeRate>) -> Self {
         Self {
             txid,
             fee_rate,
@@ -259,12 +271,17 @@ impl BumpFeeTxBuilder {
     }
 
     pub(crate) fn finish(&self, wallet: &Arc<Wallet>) -> Result<Arc<Psbt>, CreateTxError> {
-        let txid = Txid::from_str(self.txid.as_str()).map_err(|_| CreateTxError::UnknownUtxo {
-            outpoint: self.txid.clone(),
-        })?;
-        let mut wallet = wallet.get_wallet();
-        let mut tx_builder = wallet.build_fee_bump(txid).map_err(CreateTxError::from)?;
+        let frozen = wallet.frozen_outpoints();
+        let mut bdk_wallet = wallet.get_wallet();
+        let mut tx_builder = bdk_wallet
+            .build_fee_bump(self.txid)
+            .map_err(CreateTxError::from)?;
         tx_builder.fee_rate(self.fee_rate.0);
+        // Inputs of the original transaction are always kept, frozen outputs are only excluded
+        // from the extra inputs the bump may need.
+        if !frozen.is_empty() {
+            tx_builder.unspendable(frozen.into_iter().collect());
+        }
         if let Some(rbf) = &self.rbf {
             match *rbf {
                 RbfValue::Default => {
@@ -275,8 +292,84 @@ impl BumpFeeTxBuilder {
                 }
             }
         }
-        let psbt: BdkPsbt = tx_builder.finish()?;
+        let psbt = tx_builder.finish();
+        wallet.record_staged(&mut bdk_wallet);
+        let psbt: BdkPsbt = psbt?;
 
         Ok(Arc::new(psbt.into()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::CreateTxError;
+    use crate::test_utils::{confirmed, foreign_outpoint, new_persisted_wallet, new_tx};
+    use crate::tx_builder::TxBuilder;
+    use crate::types::{Label, LabelType};
+
+    use bitcoin_ffi::Script;
+
+    use bdk_wallet::bitcoin::{OutPoint, ScriptBuf};
+    use bdk_wallet::KeychainKind;
+
+    #[test]
+    fn test_finish_excludes_frozen_utxos() {
+        let wallet = new_persisted_wallet();
+        let funding = {
+            let mut bdk_wallet = wallet.get_wallet();
+            let address = bdk_wallet
+                .reveal_next_address(KeychainKind::External)
+                .script_pubkey();
+            let funding = new_tx(
+                &[foreign_outpoint(1)],
+                vec![
+                    (address.clone(), 50_000),
+                    (address.clone(), 30_000),
+                    (address, 20_000),
+                ],
+            );
+            let update = confirmed(&bdk_wallet, &[&funding], 1, 1_000);
+            bdk_wallet.apply_update(update).unwrap();
+            funding.compute_txid()
+        };
+        let (spendable, frozen, labelled) = (
+            OutPoint::new(funding, 0),
+            OutPoint::new(funding, 1),
+            OutPoint::new(funding, 2),
+        );
+        wallet.freeze_utxo(frozen, "kyc".to_string()).unwrap();
+        wallet
+            .set_label(Label {
+                label_type: LabelType::Output,
+                reference: labelled.to_string(),
+                label: "cold storage".to_string(),
+                origin: None,
+                spendable: Some(false),
+            })
+            .unwrap();
+
+        let psbt = TxBuilder::new()
+            .drain_wallet()
+            .drain_to(&Script(ScriptBuf::new()))
+            .finish(&wallet)
+            .unwrap();
+        let inputs: Vec<OutPoint> = psbt
+            .0
+            .lock()
+            .unwrap()
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|input| input.previous_output)
+            .collect();
+        assert_eq!(inputs, vec![spendable]);
+
+        for outpoint in [frozen, labelled] {
+            let result = TxBuilder::new()
+                .add_utxo(outpoint)
+                .drain_to(&Script(ScriptBuf::new()))
+                .finish(&wallet);
+            assert!(matches!(result, Err(CreateTxError::FrozenUtxo { .. })));
+        }
+    }
+}
@@ -2,11 +2,16 @@ use crate::bitcoin::{Address, Transaction, TxOut};
 use crate::error::RequestBuilderError;
 
 use bitcoin_ffi::Amount;
+use bitcoin_ffi::FeeRate;
 use bitcoin_ffi::OutPoint;
 use bitcoin_ffi::Script;
 
 use bdk_core::spk_client::SyncItem;
+use bdk_wallet::bitcoin::relative::LockTime as BdkRelativeLockTime;
+use bdk_wallet::bitcoin::Amount as BdkAmount;
+use bdk_wallet::bitcoin::ScriptBuf as BdkScriptBuf;
 use bdk_wallet::bitcoin::Transaction as BdkTransaction;
+use bdk_wallet::bitcoin::{Sequence, Txid, Wtxid};
 use bdk_wallet::chain::spk_client::FullScanRequest as BdkFullScanRequest;
 use bdk_wallet::chain::spk_client::FullScanRequestBuilder as BdkFullScanRequestBuilder;
 use bdk_wallet::chain::spk_client::SyncRequest as BdkSyncRequest;
@@ -20,7 +25,9 @@ use bdk_wallet::Balance as BdkBalance;
 use bdk_wallet::KeychainKind;
 use bdk_wallet::LocalOutput as BdkLocalOutput;
 use bdk_wallet::Update as BdkUpdate;
+use bdk_wallet::Wallet as BdkWallet;
 
+use std::borrow::Borrow;
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug)]
@@ -30,6 +37,9 @@ pub enum ChainPosition {
     },
     Unconfirmed {
         timestamp: u64,
+        /// The transaction that evicted this one from the canonical history. Only set by
+        /// `Wallet::replaced_transactions`.
+        replaced_by: Option<Txid>,
     },
 }
 
@@ -48,12 +58,15 @@ pub struct BlockId {
 pub struct CanonicalTx {
     pub transaction: Arc<Transaction>,
     pub chain_position: ChainPosition,
+    /// The wallet's BIP-329 label for this transaction, if any.
+    pub label: Option<String>,
 }
 
-impl From<BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>> for CanonicalTx {
-    fn from(tx: BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>) -> Self {
-        let chain_position = match tx.chain_position {
+impl<A: Borrow<BdkConfirmationBlockTime>> From<BdkChainPosition<A>> for ChainPosition {
+    fn from(chain_position: BdkChainPosition<A>) -> Self {
+        match chain_position {
             BdkChainPosition::Confirmed(anchor) => {
+                let anchor = anchor.borrow();
                 let block_id = BlockId {
                     height: anchor.block_id.height,
                     hash: anchor.block_id.hash.to_string(),
@@ -65,12 +78,20 @@ impl From<BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>> for
                     },
                 }
             }
-            BdkChainPosition::Unconfirmed(timestamp) => ChainPosition::Unconfirmed { timestamp },
-        };
+            BdkChainPosition::Unconfirmed(timestamp) => ChainPosition::Unconfirmed {
+                timestamp,
+                replaced_by: None,
+            },
+        }
+    }
+}
 
+impl From<BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>> for CanonicalTx {
+    fn from(tx: BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>) -> Self {
         CanonicalTx {
             transaction: Arc::new(Transaction::from(tx.tx_node.tx.as_ref().clone())),
-            chain_position,
+            chain_position: tx.chain_position.into(),
+            label: None,
         }
     }
 }
@@ -80,6 +101,11 @@ pub struct ScriptAmount {
     pub amount: Arc<Amount>,
 }
 
+pub struct PaymentUriParam {
+    pub key: String,
+    pub value: String,
+}
+
 pub struct AddressInfo {
     pub index: u32,
     pub address: Arc<Address>,
@@ -103,10 +129,12 @@ pub struct Balance {
     pub confirmed: Arc<Amount>,
     pub trusted_spendable: Arc<Amount>,
     pub total: Arc<Amount>,
+    /// Value of frozen outputs, which is also counted in the amounts above.
+    pub frozen: Arc<Amount>,
 }
 
-impl From<BdkBalance> for Balance {
-    fn from(bdk_balance: BdkBalance) -> Self {
+impl Balance {
+    pub(crate) fn new(bdk_balance: BdkBalance, frozen: BdkAmount) -> Self {
         Balance {
             immature: Arc::new(bdk_balance.immature.into()),
             trusted_pending: Arc::new(bdk_balance.trusted_pending.into()),
@@ -114,19 +142,89 @@ impl From<BdkBalance> for Balance {
             confirmed: Arc::new(bdk_balance.confirmed.into()),
             trusted_spendable: Arc::new(bdk_balance.trusted_spendable().into()),
             total: Arc::new(bdk_balance.total().into()),
+            frozen: Arc::new(frozen.into()),
         }
     }
 }
 
+pub struct AddressBalance {
+    pub keychain: KeychainKind,
+    pub index: u32,
+    pub address: Arc<Address>,
+    /// Sum of every output ever received on this address, spent or not.
+    pub total_received: Arc<Amount>,
+    pub balance: Balance,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BalanceGranularity {
+    Block,
+    Day,
+    Week,
+    Month,
+}
+
+pub struct BalancePoint {
+    /// Block time for `Block` points, the UTC start of the day, week or month otherwise, and the
+    /// latest mempool sighting for the unconfirmed point at the tip.
+    pub timestamp: u64,
+    /// Height of the last block in the bucket, `None` for the unconfirmed point at the tip.
+    pub height: Option<u32>,
+    pub confirmed: Arc<Amount>,
+    pub unconfirmed_delta: i64,
+}
+
+pub struct FrozenUtxo {
+    pub outpoint: OutPoint,
+    /// The freeze reason, or the label text when `from_label` is set.
+    pub reason: String,
+    /// Frozen by an output label with `spendable: false` rather than `freeze_utxo`. Clearing it
+    /// takes a label update, `unfreeze_utxo` leaves it frozen.
+    pub from_label: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LabelType {
+    Tx,
+    Address,
+    Pubkey,
+    Input,
+    Output,
+    Xpub,
+}
+
+/// A BIP-329 label. `reference` is a txid for `Tx`, an address for `Address`, `txid:vout` for
+/// `Input` and `Output`, and the key itself for `Pubkey` and `Xpub`.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub label_type: LabelType,
+    pub reference: String,
+    pub label: String,
+    pub origin: Option<String>,
+    /// Only meaningful for `Output` labels. `Some(false)` keeps the output out of coin selection.
+    pub spendable: Option<bool>,
+}
+
 pub struct LocalOutput {
     pub outpoint: OutPoint,
     pub txout: TxOut,
     pub keychain: KeychainKind,
     pub is_spent: bool,
+    pub derivation_index: u32,
+    /// `None` if the wallet no longer has the transaction that created this output.
+    pub chain_position: Option<ChainPosition>,
+    /// The wallet's BIP-329 label for this output, if any.
+    pub label: Option<String>,
 }
 
-impl From<BdkLocalOutput> for LocalOutput {
-    fn from(local_utxo: BdkLocalOutput) -> Self {
+impl LocalOutput {
+    /// bdk's `LocalOutput` only carries the confirmation height and time, so the chain position,
+    /// which also names the block, comes from the wallet's canonical transaction.
+    pub(crate) fn new(
+        local_utxo: BdkLocalOutput,
+        chain_position: Option<ChainPosition>,
+        label: Option<String>,
+    ) -> Self {
         LocalOutput {
             outpoint: OutPoint {
                 txid: local_utxo.outpoint.txid,
@@ -138,6 +236,9 @@ impl From<BdkLocalOutput> for LocalOutput {
             },
             keychain: local_utxo.keychain,
             is_spent: local_utxo.is_spent,
+            derivation_index: local_utxo.derivation_index,
+            chain_position,
+            label,
         }
     }
 }
@@ -228,13 +329,369 @@ impl FullScanRequestBuilder {
 
 pub struct Update(pub(crate) BdkUpdate);
 
+pub struct DecodedTxIn {
+    pub previous_output: OutPoint,
+    pub is_coinbase: bool,
+    pub script_sig_asm: String,
+    pub script_sig_hex: String,
+    pub witness: Vec<String>,
+    pub sequence: u32,
+}
+
+pub struct DecodedTxOut {
+    pub n: u32,
+    pub value: u64,
+    pub script_pubkey_asm: String,
+    pub script_pubkey_hex: String,
+    pub script_type: String,
+    pub address: Option<String>,
+}
+
+pub struct DecodedTransaction {
+    pub txid: Txid,
+    pub wtxid: Wtxid,
+    pub version: i32,
+    pub lock_time: u32,
+    pub total_size: u64,
+    pub vsize: u64,
+    pub weight: u64,
+    pub input: Vec<DecodedTxIn>,
+    pub output: Vec<DecodedTxOut>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AddressType {
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    UnknownWitness { version: u8 },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RelativeLockTime {
+    Blocks { height: u16 },
+    Time { intervals: u16 },
+}
+
+pub struct SequenceInfo {
+    pub sequence: u32,
+    pub is_rbf: bool,
+    pub enables_absolute_lock_time: bool,
+    pub relative_lock_time: Option<RelativeLockTime>,
+}
+
+impl From<Sequence> for SequenceInfo {
+    fn from(sequence: Sequence) -> Self {
+        let relative_lock_time =
+            sequence
+                .to_relative_lock_time()
+                .map(|lock_time| match lock_time {
+                    BdkRelativeLockTime::Blocks(height) => RelativeLockTime::Blocks {
+                        height: height.value(),
+                    },
+                    BdkRelativeLockTime::Time(time) => RelativeLockTime::Time {
+                        intervals: time.value(),
+                    },
+                });
+        SequenceInfo {
+            sequence: sequence.0,
+            is_rbf: sequence.is_rbf(),
+            enables_absolute_lock_time: sequence.enables_absolute_lock_time(),
+            relative_lock_time,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PolicyViolation {
+    NonStandardVersion { version: i32 },
+    TxTooLarge { weight: u64 },
+    TxTooSmall { size: u64 },
+    DustOutput { index: u32, value: u64 },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExtractTxWarning {
+    AbsurdFeeRate { fee_rate: u64 },
+    MissingInputValue,
+    SendingTooMuch,
+}
+
+pub struct UncheckedExtractedTx {
+    pub transaction: Arc<Transaction>,
+    pub warning: Option<ExtractTxWarning>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SighashKind {
+    Legacy,
+    SegwitV0,
+    Taproot,
+}
+
+pub struct InputSighash {
+    pub message: Vec<u8>,
+    pub kind: SighashKind,
+    pub sighash_type: u32,
+}
+
+#[derive(Clone, Debug)]
+pub enum PsbtMap {
+    Global,
+    Input { index: u32 },
+    Output { index: u32 },
+}
+
+#[derive(Clone, Debug)]
+pub struct ProprietaryKeyValue {
+    pub prefix: Vec<u8>,
+    pub subtype: u8,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UnknownKeyValue {
+    pub key_type: u8,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
 pub struct SentAndReceivedValues {
     pub sent: Arc<Amount>,
     pub received: Arc<Amount>,
 }
 
+pub struct WalletChanges {
+    pub revision: u64,
+    /// The requested revision does not belong to this instance of the wallet, e.g. because it was
+    /// reloaded since. Everything must be fetched again, and the lists below are empty.
+    pub full_resync: bool,
+    pub txids: Vec<Txid>,
+    pub outpoints: Vec<OutPoint>,
+    pub addresses: Vec<Arc<Address>>,
+}
+
+pub struct TransactionDetails {
+    pub txid: Txid,
+    pub transaction: Arc<Transaction>,
+    pub sent: Arc<Amount>,
+    pub received: Arc<Amount>,
+    pub balance_delta: i64,
+    pub fee: Option<Arc<Amount>>,
+    pub fee_rate: Option<Arc<FeeRate>>,
+    pub chain_position: ChainPosition,
+    /// When the wallet first saw the transaction unconfirmed, `None` if it never did.
+    pub first_seen: Option<u64>,
+    pub last_seen: Option<u64>,
+    pub is_rbf: bool,
+    pub label: Option<String>,
+}
+
+impl TransactionDetails {
+    pub(crate) fn new(
+        wallet: &BdkWallet,
+        tx: BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>,
+        first_seen: Option<u64>,
+        label: Option<String>,
+    ) -> Self {
+        let transaction = tx.tx_node.tx.as_ref();
+        let (sent, received) = wallet.sent_and_received(transaction);
+        let fee = wallet.calculate_fee(transaction).ok();
+        let fee_rate = wallet.calculate_fee_rate(transaction).ok();
+        let last_seen = tx.tx_node.last_seen_unconfirmed;
+
+        TransactionDetails {
+            txid: tx.tx_node.txid,
+            transaction: Arc::new(Transaction::from(transaction.clone())),
+            sent: Arc::new(sent.into()),
+            received: Arc::new(received.into()),
+            balance_delta: received.to_sat() as i64 - sent.to_sat() as i64,
+            fee: fee.map(|fee| Arc::new(fee.into())),
+            fee_rate: fee_rate.map(|fee_rate| Arc::new(FeeRate(fee_rate))),
+            chain_position: tx.chain_position.into(),
+            first_seen,
+            last_seen,
+            is_rbf: transaction.is_explicitly_rbf(),
+            label,
+        }
+    }
+}
+
+// The variant names read as the sort order in the bindings, e.g. `TransactionSort.NEWEST_FIRST`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum TransactionSort {
+    NewestFirst,
+    OldestFirst,
+    LargestAmountFirst,
+    SmallestAmountFirst,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Csv,
+    Json,
+}
+
+/// Column layouts for `Wallet::export_history`. `Koinly` and `CoinTracker` match the generic CSV
+/// import formats of those services.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryLayout {
+    Standard,
+    Koinly,
+    CoinTracker,
+}
+
+/// Transactions that spend wallet coins are outgoing, all others are incoming.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionDirection {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfirmationStatus {
+    Confirmed,
+    Unconfirmed,
+}
+
+/// Every field that is set must match. Time bounds are inclusive and apply to the confirmation
+/// time, or to the last time an unconfirmed transaction was seen.
+pub struct TransactionFilter {
+    pub direction: Option<TransactionDirection>,
+    pub confirmation_status: Option<ConfirmationStatus>,
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+    pub keychain: Option<KeychainKind>,
+    pub min_amount: Option<Arc<Amount>>,
+}
+
+impl TransactionFilter {
+    pub(crate) fn matches(
+        &self,
+        wallet: &BdkWallet,
+        tx: &BdkCanonicalTx<'_, Arc<BdkTransaction>, BdkConfirmationBlockTime>,
+        sent: BdkAmount,
+        balance_delta: i64,
+    ) -> bool {
+        let direction = if sent > BdkAmount::ZERO {
+            TransactionDirection::Outgoing
+        } else {
+            TransactionDirection::Incoming
+        };
+        let (confirmation_status, timestamp) = match &tx.chain_position {
+            BdkChainPosition::Confirmed(anchor) => {
+                (ConfirmationStatus::Confirmed, anchor.confirmation_time)
+            }
+            BdkChainPosition::Unconfirmed(last_seen) => {
+                (ConfirmationStatus::Unconfirmed, *last_seen)
+            }
+        };
+
+        self.direction.map_or(true, |d| d == direction)
+            && self
+                .confirmation_status
+                .map_or(true, |status| status == confirmation_status)
+            && self.from_timestamp.map_or(true, |from| timestamp >= from)
+            && self.to_timestamp.map_or(true, |to| timestamp <= to)
+            && self
+                .min_amount
+                .as_ref()
+                .map_or(true, |min| balance_delta.unsigned_abs() >= min.0.to_sat())
+            && self.keychain.map_or(true, |keychain| {
+                involves_keychain(wallet, &tx.tx_node.tx, keychain)
+            })
+    }
+}
+
+fn involves_keychain(wallet: &BdkWallet, tx: &BdkTransaction, keychain: KeychainKind) -> bool {
+    let is_keychain_spk = |script: &BdkScriptBuf| {
+        wallet.derivation_of_spk(script.clone()).map(|(k, _)| k) == Some(keychain)
+    };
+    tx.output
+        .iter()
+        .any(|tx_out| is_keychain_spk(&tx_out.script_pubkey))
+        || tx.input.iter().any(|tx_in| {
+            wallet
+                .tx_graph()
+                .get_txout(tx_in.previous_output)
+                .is_some_and(|tx_out| is_keychain_spk(&tx_out.script_pubkey))
+        })
+}
+
 #[derive(Clone, Debug)]
 pub enum RbfValue {
     Default,
     Value(u32),
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{confirmed, foreign_outpoint, new_tx, new_wallet, unconfirmed};
+    use crate::types::{ChainPosition, TransactionDetails};
+
+    use bdk_wallet::bitcoin::{Amount, OutPoint, ScriptBuf};
+    use bdk_wallet::KeychainKind;
+
+    #[test]
+    fn test_transaction_details() {
+        let mut wallet = new_wallet();
+        let address = wallet
+            .reveal_next_address(KeychainKind::External)
+            .script_pubkey();
+        let change = wallet
+            .reveal_next_address(KeychainKind::Internal)
+            .script_pubkey();
+
+        let funding = new_tx(&[foreign_outpoint(1)], vec![(address, 50_000)]);
+        let update = confirmed(&wallet, &[&funding], 1, 1_000);
+        wallet.apply_update(update).unwrap();
+        let spend = new_tx(
+            &[OutPoint::new(funding.compute_txid(), 0)],
+            vec![(ScriptBuf::new(), 30_000), (change, 19_000)],
+        );
+        wallet
+            .apply_update_at(unconfirmed(&[&spend], 2_000), None)
+            .unwrap();
+
+        let details = |txid| {
+            let tx = wallet.get_tx(txid).unwrap();
+            TransactionDetails::new(&wallet, tx, Some(1_500), Some("label".to_string()))
+        };
+
+        // The funding input is not the wallet's, so its fee is unknown.
+        let incoming = details(funding.compute_txid());
+        assert_eq!(incoming.received.0, Amount::from_sat(50_000));
+        assert_eq!(incoming.sent.0, Amount::ZERO);
+        assert_eq!(incoming.balance_delta, 50_000);
+        assert!(incoming.fee.is_none());
+        assert!(incoming.fee_rate.is_none());
+        assert!(incoming.last_seen.is_none());
+        assert!(matches!(
+            incoming.chain_position,
+            ChainPosition::Confirmed { confirmation_block_time } if confirmation_block_time.confirmation_time == 1_000
+        ));
+
+        let outgoing = details(spend.compute_txid());
+        assert_eq!(outgoing.txid, spend.compute_txid());
+        assert_eq!(outgoing.sent.0, Amount::from_sat(50_000));
+        assert_eq!(outgoing.received.0, Amount::from_sat(19_000));
+        assert_eq!(outgoing.balance_delta, -31_000);
+        assert_eq!(outgoing.fee.unwrap().0, Amount::from_sat(1_000));
+        assert!(outgoing.fee_rate.is_some());
+        assert_eq!(outgoing.first_seen, Some(1_500));
+        assert_eq!(outgoing.last_seen, Some(2_000));
+        assert!(matches!(
+            outgoing.chain_position,
+            ChainPosition::Unconfirmed {
+                timestamp: 2_000,
+                ..
+            }
+        ));
+        assert!(outgoing.is_rbf);
+        assert_eq!(outgoing.label.as_deref(), Some("label"));
+    }
+}
@@ -1,27 +1,47 @@
+use crate::balances;
 use crate::bitcoin::{Psbt, Transaction};
+use crate::coin_control;
+use crate::conflicts;
 use crate::descriptor::Descriptor;
 use crate::error::{
-    CalculateFeeError, CannotConnectError, CreateWithPersistError, LoadWithPersistError,
-    SignerError, SqliteError, TxidParseError,
+    CalculateFeeError, CannotConnectError, CreateWithPersistError, FreezeUtxoError,
+    HistoryExportError, LabelError, LoadWithPersistError, SignerError, SqliteError,
 };
+use crate::first_seen::FirstSeen;
+use crate::history_export;
+use crate::labels::{self, LabelStore};
+use crate::revision::{self, RevisionLog};
 use crate::store::Connection;
 use crate::types::{
-    AddressInfo, Balance, CanonicalTx, FullScanRequestBuilder, LocalOutput, SentAndReceivedValues,
-    SyncRequestBuilder, Update,
+    AddressBalance, AddressInfo, Balance, BalanceGranularity, BalancePoint, CanonicalTx,
+    ChainPosition, FrozenUtxo, FullScanRequestBuilder, HistoryFormat, HistoryLayout, Label,
+    LabelType, LocalOutput, SentAndReceivedValues, SyncRequestBuilder, TransactionDetails,
+    TransactionFilter, TransactionSort, Update, WalletChanges,
 };
 
-use bitcoin_ffi::{Amount, FeeRate, Script};
+use bitcoin_ffi::{Amount, FeeRate, OutPoint, Script};
 
+use bdk_wallet::bitcoin::OutPoint as BdkOutPoint;
+use bdk_wallet::bitcoin::Transaction as BdkTransaction;
 use bdk_wallet::bitcoin::{Network, Txid};
+use bdk_wallet::chain::Merge;
 use bdk_wallet::rusqlite::Connection as BdkConnection;
-use bdk_wallet::{KeychainKind, PersistedWallet, SignOptions, Wallet as BdkWallet};
+use bdk_wallet::{
+    KeychainKind, LocalOutput as BdkLocalOutput, PersistedWallet, SignOptions, Wallet as BdkWallet,
+    WalletPersister,
+};
 
 use std::borrow::BorrowMut;
-use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::{Arc, Mutex, MutexGuard};
 
 pub struct Wallet {
     inner_mutex: Mutex<PersistedWallet<BdkConnection>>,
+    revision_log: Mutex<RevisionLog>,
+    connection: Arc<Connection>,
+    frozen_utxos: Mutex<BTreeMap<BdkOutPoint, String>>,
+    labels: Mutex<LabelStore>,
+    first_seen: Mutex<FirstSeen>,
 }
 
 impl Wallet {
@@ -40,9 +60,28 @@ impl Wallet {
             BdkWallet::create(descriptor, change_descriptor)
                 .network(network)
                 .create_wallet(db)?;
+        let frozen_utxos =
+            coin_control::load_frozen_utxos(db).map_err(|e| CreateWithPersistError::Persist {
+                error_message: e.to_string(),
+            })?;
+        let labels = LabelStore::load(db).map_err(|e| CreateWithPersistError::Persist {
+            error_message: e.to_string(),
+        })?;
+        let first_seen = FirstSeen::load(db).map_err(|e| CreateWithPersistError::Persist {
+            error_message: e.to_string(),
+        })?;
+        let epoch = revision::next_epoch(db).map_err(|e| CreateWithPersistError::Persist {
+            error_message: e.to_string(),
+        })?;
+        let revision_log = RevisionLog::new(&wallet, epoch);
 
         Ok(Wallet {
             inner_mutex: Mutex::new(wallet),
+            revision_log: Mutex::new(revision_log),
+            connection: Arc::clone(&connection),
+            frozen_utxos: Mutex::new(frozen_utxos),
+            labels: Mutex::new(labels),
+            first_seen: Mutex::new(first_seen),
         })
     }
 
@@ -62,9 +101,29 @@ impl Wallet {
             .extract_keys()
             .load_wallet(db)?
             .ok_or(LoadWithPersistError::CouldNotLoad)?;
+        let frozen_utxos =
+            coin_control::load_frozen_utxos(db).map_err(|e| LoadWithPersistError::Persist {
+                error_message: e.to_string(),
+            })?;
+        let labels = LabelStore::load(db).map_err(|e| LoadWithPersistError::Persist {
+            error_message: e.to_string(),
+        })?;
+        let mut first_seen = FirstSeen::load(db).map_err(|e| LoadWithPersistError::Persist {
+            error_message: e.to_string(),
+        })?;
+        first_seen.record(&wallet);
+        let epoch = revision::next_epoch(db).map_err(|e| LoadWithPersistError::Persist {
+            error_message: e.to_string(),
+        })?;
+        let revision_log = RevisionLog::new(&wallet, epoch);
 
         Ok(Wallet {
             inner_mutex: Mutex::new(wallet),
+            revision_log: Mutex::new(revision_log),
+            connection: Arc::clone(&connection),
+            frozen_utxos: Mutex::new(frozen_utxos),
+            labels: Mutex::new(labels),
+            first_seen: Mutex::new(first_seen),
         })
     }
 
@@ -72,14 +131,46 @@ impl Wallet {
         self.inner_mutex.lock().expect("wallet")
     }
 
+    fn get_first_seen(&self) -> MutexGuard<FirstSeen> {
+        self.first_seen.lock().expect("first seen")
+    }
+
+    pub(crate) fn get_revision_log(&self) -> MutexGuard<RevisionLog> {
+        self.revision_log.lock().expect("revision log")
+    }
+
+    /// Records what the last operation on `wallet` changed. Must be called after every operation
+    /// that stages changes, while still holding the wallet.
+    pub(crate) fn record_staged(&self, wallet: &mut BdkWallet) {
+        self.get_revision_log().record_staged(wallet);
+    }
+
     pub fn reveal_next_address(&self, keychain_kind: KeychainKind) -> AddressInfo {
-        self.get_wallet().reveal_next_address(keychain_kind).into()
+        let mut wallet = self.get_wallet();
+        let address_info = wallet.reveal_next_address(keychain_kind);
+        self.record_staged(&mut wallet);
+        address_info.into()
     }
 
     pub fn apply_update(&self, update: Arc<Update>) -> Result<(), CannotConnectError> {
-        self.get_wallet()
+        let mut wallet = self.get_wallet();
+        wallet
             .apply_update(update.0.clone())
-            .map_err(CannotConnectError::from)
+            .map_err(CannotConnectError::from)?;
+        self.get_first_seen().record(&wallet);
+        self.record_staged(&mut wallet);
+        Ok(())
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.get_revision_log().revision()
+    }
+
+    /// Transactions, outputs and addresses that changed after `revision`, along with the current
+    /// revision to pass on the next call.
+    pub fn changes_since(&self, revision: u64) -> WalletChanges {
+        let network = self.get_wallet().network();
+        self.get_revision_log().changes_since(revision, network)
     }
 
     pub(crate) fn derivation_index(&self, keychain: KeychainKind) -> Option<u32> {
@@ -91,8 +182,195 @@ impl Wallet {
     }
 
     pub fn balance(&self) -> Balance {
-        let bdk_balance = self.get_wallet().balance();
-        Balance::from(bdk_balance)
+        let frozen_outpoints = self.frozen_outpoints();
+        let wallet = self.get_wallet();
+        let frozen = balances::unspent_value(&wallet, &frozen_outpoints, |_| true);
+        Balance::new(wallet.balance(), frozen)
+    }
+
+    pub fn balance_by_keychain(&self, keychain: KeychainKind) -> Balance {
+        let frozen_outpoints = self.frozen_outpoints();
+        let wallet = self.get_wallet();
+        let frozen = balances::unspent_value(&wallet, &frozen_outpoints, |output| {
+            output.keychain == keychain
+        });
+        Balance::new(balances::keychain_balance(&wallet, keychain), frozen)
+    }
+
+    /// One entry per revealed address of `keychain`, in derivation order.
+    pub fn address_balances(&self, keychain: KeychainKind) -> Vec<AddressBalance> {
+        let frozen_outpoints = self.frozen_outpoints();
+        balances::address_balances(&self.get_wallet(), keychain, &frozen_outpoints)
+    }
+
+    /// Like `balance`, but outputs need at least `min_confirmations` confirmations to count as
+    /// confirmed. Shallower outputs are reported as trusted or untrusted pending.
+    pub fn balance_with_min_confirmations(&self, min_confirmations: u32) -> Balance {
+        let frozen_outpoints = self.frozen_outpoints();
+        let wallet = self.get_wallet();
+        let frozen = balances::unspent_value(&wallet, &frozen_outpoints, |_| true);
+        Balance::new(
+            balances::balance_with_min_confirmations(&wallet, min_confirmations),
+            frozen,
+        )
+    }
+
+    /// Confirmed balance over time, for charts. Transactions replaced through RBF are left out.
+    pub fn balance_history(&self, granularity: BalanceGranularity) -> Vec<BalancePoint> {
+        balances::balance_history(&self.get_wallet(), granularity)
+    }
+
+    /// Frozen outputs are never selected by `TxBuilder` or `BumpFeeTxBuilder`. The freeze is
+    /// stored in the wallet's SQLite database right away and survives restarts.
+    /// Only unspent outputs the wallet owns can be frozen.
+    pub fn freeze_utxo(&self, outpoint: OutPoint, reason: String) -> Result<(), FreezeUtxoError> {
+        let is_spent = self
+            .get_wallet()
+            .list_output()
+            .find(|output| output.outpoint == outpoint)
+            .map(|output| output.is_spent);
+        match is_spent {
+            None => {
+                return Err(FreezeUtxoError::UnknownUtxo {
+                    outpoint: outpoint.to_string(),
+                })
+            }
+            Some(true) => {
+                return Err(FreezeUtxoError::SpentUtxo {
+                    outpoint: outpoint.to_string(),
+                })
+            }
+            Some(false) => {}
+        }
+        coin_control::freeze_utxo(&self.connection.get_store(), &outpoint, &reason)?;
+        self.get_frozen_utxos().insert(outpoint, reason);
+        self.get_revision_log()
+            .record(Vec::new(), vec![outpoint], Vec::new());
+        Ok(())
+    }
+
+    pub fn unfreeze_utxo(&self, outpoint: OutPoint) -> Result<bool, SqliteError> {
+        let was_frozen = coin_control::unfreeze_utxo(&self.connection.get_store(), &outpoint)?;
+        self.get_frozen_utxos().remove(&outpoint);
+        if was_frozen {
+            self.get_revision_log()
+                .record(Vec::new(), vec![outpoint], Vec::new());
+        }
+        Ok(was_frozen)
+    }
+
+    /// Every output coin selection skips: those frozen with `freeze_utxo` and those labelled
+    /// `spendable: false`, sorted by outpoint.
+    pub fn list_frozen(&self) -> Vec<FrozenUtxo> {
+        let mut frozen: BTreeMap<BdkOutPoint, FrozenUtxo> = self
+            .get_frozen_utxos()
+            .iter()
+            .map(|(outpoint, reason)| {
+                let frozen_utxo = FrozenUtxo {
+                    outpoint: *outpoint,
+                    reason: reason.clone(),
+                    from_label: false,
+                };
+                (*outpoint, frozen_utxo)
+            })
+            .collect();
+        for (outpoint, label) in self.get_labels().unspendable_outputs() {
+            frozen.entry(outpoint).or_insert_with(|| FrozenUtxo {
+                outpoint,
+                reason: label.label.clone(),
+                from_label: true,
+            });
+        }
+        frozen.into_values().collect()
+    }
+
+    /// Outputs coin selection must skip: frozen ones and those labelled `spendable: false`.
+    pub(crate) fn frozen_outpoints(&self) -> HashSet<BdkOutPoint> {
+        let mut outpoints: HashSet<BdkOutPoint> = self.get_frozen_utxos().keys().copied().collect();
+        outpoints.extend(
+            self.get_labels()
+                .unspendable_outputs()
+                .map(|(outpoint, _)| outpoint),
+        );
+        outpoints
+    }
+
+    fn get_frozen_utxos(&self) -> MutexGuard<BTreeMap<BdkOutPoint, String>> {
+        self.frozen_utxos.lock().expect("frozen utxos")
+    }
+
+    /// Labels are stored in the wallet's SQLite database right away.
+    pub fn set_label(&self, label: Label) -> Result<(), SqliteError> {
+        let (label_type, reference) = (label.label_type, label.reference.clone());
+        self.get_labels()
+            .set(&self.connection.get_store(), vec![label])?;
+        self.get_revision_log()
+            .record_labels([(label_type, reference.as_str())]);
+        Ok(())
+    }
+
+    pub fn remove_label(
+        &self,
+        label_type: LabelType,
+        reference: String,
+    ) -> Result<bool, SqliteError> {
+        let removed =
+            self.get_labels()
+                .remove(&self.connection.get_store(), label_type, &reference)?;
+        if removed {
+            self.get_revision_log()
+                .record_labels([(label_type, reference.as_str())]);
+        }
+        Ok(removed)
+    }
+
+    pub fn get_label(&self, label_type: LabelType, reference: String) -> Option<Label> {
+        self.get_labels().get(label_type, &reference).cloned()
+    }
+
+    pub fn list_labels(&self) -> Vec<Label> {
+        self.get_labels().list()
+    }
+
+    /// All labels as BIP-329 JSON Lines.
+    pub fn export_labels(&self) -> String {
+        self.get_labels().export()
+    }
+
+    /// Imports BIP-329 JSON Lines, replacing existing labels with the same type and reference.
+    /// Nothing is imported if any line is invalid. Returns the number of labels imported.
+    pub fn import_labels(&self, jsonl: String) -> Result<u32, LabelError> {
+        let labels = labels::parse_jsonl(&jsonl)?;
+        // A later record for the same type and reference replaces an earlier one.
+        let references: BTreeSet<(LabelType, String)> = labels
+            .iter()
+            .map(|label| (label.label_type, label.reference.clone()))
+            .collect();
+        let count = references.len() as u32;
+        self.get_labels()
+            .set(&self.connection.get_store(), labels)?;
+        self.get_revision_log().record_labels(
+            references
+                .iter()
+                .map(|(label_type, reference)| (*label_type, reference.as_str())),
+        );
+        Ok(count)
+    }
+
+    fn get_labels(&self) -> MutexGuard<LabelStore> {
+        self.labels.lock().expect("labels")
+    }
+
+    fn labelled_output(
+        wallet: &BdkWallet,
+        labels: &LabelStore,
+        output: BdkLocalOutput,
+    ) -> LocalOutput {
+        let chain_position = wallet
+            .get_tx(output.outpoint.txid)
+            .map(|tx| tx.chain_position.into());
+        let label = labels.label_of(LabelType::Output, &output.outpoint.to_string());
+        LocalOutput::new(output, chain_position, label)
     }
 
     pub fn is_mine(&self, script: Arc<Script>) -> bool {
@@ -110,6 +388,30 @@ impl Wallet {
             .map_err(SignerError::from)
     }
 
+    /// Releases the change addresses a built but abandoned transaction took, so the next
+    /// transaction reuses them instead of growing the gap. UTXOs are never reserved while
+    /// building, so there are none to release: the inputs of an unbroadcast transaction stay
+    /// spendable throughout. Only call this for transactions that were never broadcast.
+    pub fn cancel_tx(&self, tx: &Transaction) {
+        let tx: BdkTransaction = tx.into();
+        let mut wallet = self.get_wallet();
+        wallet.cancel_tx(&tx);
+        // Releasing addresses is not staged, so the released change addresses are recorded here.
+        let scripts = tx
+            .output
+            .into_iter()
+            .map(|output| output.script_pubkey)
+            .filter(|script| {
+                matches!(
+                    wallet.derivation_of_spk(script.clone()),
+                    Some((KeychainKind::Internal, _))
+                )
+            })
+            .collect();
+        self.get_revision_log()
+            .record(Vec::new(), Vec::new(), scripts);
+    }
+
     pub fn sent_and_received(&self, tx: &Transaction) -> SentAndReceivedValues {
         let (sent, received) = self.get_wallet().sent_and_received(&tx.into());
         SentAndReceivedValues {
@@ -119,16 +421,128 @@ impl Wallet {
     }
 
     pub fn transactions(&self) -> Vec<CanonicalTx> {
+        let labels = self.get_labels();
         self.get_wallet()
             .transactions()
-            .map(|tx| tx.into())
+            .map(|tx| CanonicalTx {
+                label: labels.label_of(LabelType::Tx, &tx.tx_node.txid.to_string()),
+                ..tx.into()
+            })
+            .collect()
+    }
+
+    pub fn transaction_details(&self) -> Vec<TransactionDetails> {
+        let labels = self.get_labels();
+        let wallet = self.get_wallet();
+        let first_seen = self.get_first_seen();
+        wallet
+            .transactions()
+            .map(|tx| {
+                let label = labels.label_of(LabelType::Tx, &tx.tx_node.txid.to_string());
+                let first_seen = first_seen.get(tx.tx_node.txid);
+                TransactionDetails::new(&wallet, tx, first_seen, label)
+            })
             .collect()
     }
 
-    pub fn get_tx(&self, txid: String) -> Result<Option<CanonicalTx>, TxidParseError> {
-        let txid =
-            Txid::from_str(txid.as_str()).map_err(|_| TxidParseError::InvalidTxid { txid })?;
-        Ok(self.get_wallet().get_tx(txid).map(|tx| tx.into()))
+    pub fn transactions_page(
+        &self,
+        offset: u32,
+        limit: u32,
+        sort: TransactionSort,
+        filter: TransactionFilter,
+    ) -> Vec<TransactionDetails> {
+        let labels = self.get_labels();
+        let wallet = self.get_wallet();
+        // Filter and sort on cheap values first so only the requested page is fully built.
+        let mut entries: Vec<_> = wallet
+            .transactions()
+            .filter_map(|tx| {
+                let (sent, received) = wallet.sent_and_received(&tx.tx_node.tx);
+                let balance_delta = received.to_sat() as i64 - sent.to_sat() as i64;
+                filter
+                    .matches(&wallet, &tx, sent, balance_delta)
+                    .then_some((tx, balance_delta))
+            })
+            .collect();
+
+        entries.sort_by(|(tx_a, delta_a), (tx_b, delta_b)| {
+            let by_position = tx_a
+                .chain_position
+                .cmp(&tx_b.chain_position)
+                .then_with(|| tx_a.tx_node.txid.cmp(&tx_b.tx_node.txid));
+            let by_amount = delta_a.unsigned_abs().cmp(&delta_b.unsigned_abs());
+            match sort {
+                TransactionSort::NewestFirst => by_position.reverse(),
+                TransactionSort::OldestFirst => by_position,
+                TransactionSort::LargestAmountFirst => by_amount.reverse().then(by_position),
+                TransactionSort::SmallestAmountFirst => by_amount.then(by_position),
+            }
+        });
+
+        let first_seen = self.get_first_seen();
+        entries
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(tx, _)| {
+                let label = labels.label_of(LabelType::Tx, &tx.tx_node.txid.to_string());
+                let first_seen = first_seen.get(tx.tx_node.txid);
+                TransactionDetails::new(&wallet, tx, first_seen, label)
+            })
+            .collect()
+    }
+
+    /// Other wallet transactions spending any of the same outputs as `txid`, canonical or not.
+    pub fn conflicts(&self, txid: Txid) -> Vec<Txid> {
+        conflicts::conflicts(&self.get_wallet(), txid)
+    }
+
+    /// The canonical transaction that replaced `txid`, following replacements that were replaced
+    /// in turn. Falls back to a direct conflict if none of them is canonical, and is `None` if
+    /// `txid` is canonical or unknown.
+    pub fn replaced_by(&self, txid: Txid) -> Option<Txid> {
+        conflicts::replaced_by(&self.get_wallet(), txid)
+    }
+
+    pub fn is_replaceable(&self, txid: Txid) -> bool {
+        conflicts::is_replaceable(&self.get_wallet(), txid)
+    }
+
+    /// Transactions that were evicted from `transactions()` by a conflicting transaction. Their
+    /// chain position is `Unconfirmed` with `replaced_by` set.
+    pub fn replaced_transactions(&self) -> Vec<CanonicalTx> {
+        let labels = self.get_labels();
+        conflicts::replaced_transactions(&self.get_wallet())
+            .into_iter()
+            .map(|(tx, last_seen, replacement)| CanonicalTx {
+                label: labels.label_of(LabelType::Tx, &tx.compute_txid().to_string()),
+                transaction: Arc::new(Transaction::from(tx.as_ref().clone())),
+                chain_position: ChainPosition::Unconfirmed {
+                    timestamp: last_seen,
+                    replaced_by: Some(replacement),
+                },
+            })
+            .collect()
+    }
+
+    /// Transaction history for accounting, as CSV with a header row or as a JSON array with one
+    /// object per transaction. Amounts are in BTC and dates in UTC. Fails if a transaction spends
+    /// wallet outputs of transactions the wallet does not hold, as its balance would go negative.
+    pub fn export_history(
+        &self,
+        format: HistoryFormat,
+        layout: HistoryLayout,
+    ) -> Result<String, HistoryExportError> {
+        let labels = self.get_labels();
+        history_export::export_history(&self.get_wallet(), &labels, format, layout)
+    }
+
+    pub fn get_tx(&self, txid: Txid) -> Option<CanonicalTx> {
+        let label = self.get_labels().label_of(LabelType::Tx, &txid.to_string());
+        self.get_wallet()
+            .get_tx(txid)
+            .map(|tx| CanonicalTx { label, ..tx.into() })
     }
 
     pub fn calculate_fee(&self, tx: &Transaction) -> Result<Arc<Amount>, CalculateFeeError> {
@@ -147,11 +561,29 @@ impl Wallet {
     }
 
     pub fn list_unspent(&self) -> Vec<LocalOutput> {
-        self.get_wallet().list_unspent().map(|o| o.into()).collect()
+        let labels = self.get_labels();
+        let wallet = self.get_wallet();
+        wallet
+            .list_unspent()
+            .map(|o| Wallet::labelled_output(&wallet, &labels, o))
+            .collect()
     }
 
     pub fn list_output(&self) -> Vec<LocalOutput> {
-        self.get_wallet().list_output().map(|o| o.into()).collect()
+        let labels = self.get_labels();
+        let wallet = self.get_wallet();
+        wallet
+            .list_output()
+            .map(|o| Wallet::labelled_output(&wallet, &labels, o))
+            .collect()
+    }
+
+    pub fn get_utxo(&self, outpoint: OutPoint) -> Option<LocalOutput> {
+        let labels = self.get_labels();
+        let wallet = self.get_wallet();
+        wallet
+            .get_utxo(outpoint)
+            .map(|o| Wallet::labelled_output(&wallet, &labels, o))
     }
 
     pub fn start_full_scan(&self) -> Arc<FullScanRequestBuilder> {
@@ -168,10 +600,161 @@ impl Wallet {
     pub fn persist(&self, connection: Arc<Connection>) -> Result<bool, SqliteError> {
         let mut binding = connection.get_store();
         let db: &mut BdkConnection = binding.borrow_mut();
-        self.get_wallet()
-            .persist(db)
-            .map_err(|e| SqliteError::Sqlite {
+        let mut wallet = self.get_wallet();
+        let mut revision_log = self.get_revision_log();
+        let first_seen_written =
+            self.get_first_seen()
+                .persist(db)
+                .map_err(|e| SqliteError::Sqlite {
+                    rusqlite_error: e.to_string(),
+                })?;
+        // The revision log holds the changes staged since the last persist.
+        let changeset = revision_log.take_unpersisted(&mut wallet);
+        if changeset.is_empty() {
+            return Ok(first_seen_written);
+        }
+        if let Err(e) = BdkConnection::persist(db, &changeset) {
+            revision_log.restore_unpersisted(changeset);
+            return Err(SqliteError::Sqlite {
                 rusqlite_error: e.to_string(),
+            });
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitcoin::Transaction;
+    use crate::error::FreezeUtxoError;
+    use crate::test_utils::{
+        confirmed, foreign_outpoint, new_persisted_wallet, new_tx, unconfirmed,
+    };
+    use crate::tx_builder::TxBuilder;
+    use crate::types::{Label, LabelType};
+
+    use bitcoin_ffi::{Amount, Script};
+
+    use bdk_wallet::bitcoin::{OutPoint, ScriptBuf};
+    use bdk_wallet::KeychainKind;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_freeze_utxo() {
+        let wallet = new_persisted_wallet();
+        let (unspent, spent) = {
+            let mut bdk_wallet = wallet.get_wallet();
+            let address = bdk_wallet
+                .reveal_next_address(KeychainKind::External)
+                .script_pubkey();
+            let funding = new_tx(
+                &[foreign_outpoint(1)],
+                vec![(address.clone(), 50_000), (address, 20_000)],
+            );
+            let update = confirmed(&bdk_wallet, &[&funding], 1, 1_000);
+            bdk_wallet.apply_update(update).unwrap();
+            let spent = OutPoint::new(funding.compute_txid(), 1);
+            let spending = new_tx(&[spent], vec![(ScriptBuf::new(), 19_000)]);
+            bdk_wallet
+                .apply_update_at(unconfirmed(&[&spending], 2_000), None)
+                .unwrap();
+            (OutPoint::new(funding.compute_txid(), 0), spent)
+        };
+
+        assert!(matches!(
+            wallet.freeze_utxo(foreign_outpoint(2), "dust".to_string()),
+            Err(FreezeUtxoError::UnknownUtxo { .. })
+        ));
+        assert!(matches!(
+            wallet.freeze_utxo(spent, "dust".to_string()),
+            Err(FreezeUtxoError::SpentUtxo { .. })
+        ));
+        assert!(wallet.list_frozen().is_empty());
+
+        wallet.freeze_utxo(unspent, "kyc".to_string()).unwrap();
+        // Labels may mark any output unspendable, owned or not.
+        wallet
+            .set_label(Label {
+                label_type: LabelType::Output,
+                reference: foreign_outpoint(3).to_string(),
+                label: "cold storage".to_string(),
+                origin: None,
+                spendable: Some(false),
             })
+            .unwrap();
+
+        let frozen: Vec<_> = wallet
+            .list_frozen()
+            .into_iter()
+            .map(|frozen| (frozen.outpoint, frozen.reason, frozen.from_label))
+            .collect();
+        let mut expected = vec![
+            (unspent, "kyc".to_string(), false),
+            (foreign_outpoint(3), "cold storage".to_string(), true),
+        ];
+        expected.sort_unstable();
+        assert_eq!(frozen, expected);
+    }
+
+    #[test]
+    fn test_cancel_tx_reuses_change_address() {
+        let wallet = new_persisted_wallet();
+        {
+            let mut bdk_wallet = wallet.get_wallet();
+            let address = bdk_wallet
+                .reveal_next_address(KeychainKind::External)
+                .script_pubkey();
+            let funding = new_tx(&[foreign_outpoint(1)], vec![(address, 50_000)]);
+            let update = confirmed(&bdk_wallet, &[&funding], 1, 1_000);
+            bdk_wallet.apply_update(update).unwrap();
+        }
+        let build = || {
+            let psbt = TxBuilder::new()
+                .add_recipient(
+                    &Script(ScriptBuf::new()),
+                    Arc::new(Amount::from_sat(10_000)),
+                )
+                .finish(&wallet)
+                .unwrap();
+            let tx = psbt.0.lock().unwrap().unsigned_tx.clone();
+            let change = tx
+                .output
+                .iter()
+                .find_map(|output| {
+                    wallet
+                        .get_wallet()
+                        .derivation_of_spk(output.script_pubkey.clone())
+                })
+                .unwrap();
+            (Transaction::from(tx), change)
+        };
+
+        let (abandoned, change) = build();
+        assert_eq!(change, (KeychainKind::Internal, 0));
+        wallet.cancel_tx(&abandoned);
+        let (_, change) = build();
+        assert_eq!(change, (KeychainKind::Internal, 0));
+        // Without cancelling, the next transaction takes a new change address.
+        let (_, change) = build();
+        assert_eq!(change, (KeychainKind::Internal, 1));
+    }
+
+    #[test]
+    fn test_import_labels_counts_distinct_keys() {
+        let wallet = new_persisted_wallet();
+        let txid = foreign_outpoint(1).txid;
+        let jsonl = format!(
+            "{{\"type\":\"tx\",\"ref\":\"{txid}\",\"label\":\"rent\"}}\n\
+             {{\"type\":\"tx\",\"ref\":\"{txid}\",\"label\":\"rent, march\"}}\n\
+             {{\"type\":\"output\",\"ref\":\"{txid}:0\",\"label\":\"rent\"}}\n"
+        );
+
+        assert_eq!(wallet.import_labels(jsonl).unwrap(), 2);
+        let labels = wallet.list_labels();
+        assert_eq!(labels.len(), 2);
+        assert!(labels
+            .iter()
+            .any(|label| label.label_type == LabelType::Tx && label.label == "rent, march"));
     }
 }
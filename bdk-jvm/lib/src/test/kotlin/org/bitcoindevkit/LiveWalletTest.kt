@@ -2,6 +2,7 @@ package org.bitcoindevkit
 
 import kotlin.test.AfterTest
 import kotlin.test.Test
+import kotlin.test.assertNull
 import kotlin.test.assertTrue
 import java.io.File
 import org.rustbitcoin.bitcoin.Amount
@@ -55,6 +56,10 @@ class LiveWalletTest {
             println("Transaction: ${tx.transaction.computeTxid()}")
             println("Sent ${sentAndReceived.sent}")
             println("Received ${sentAndReceived.received}")
+            val chainPosition = tx.chainPosition
+            if (chainPosition is ChainPosition.Unconfirmed) {
+                assertNull(chainPosition.replacedBy, "Transactions in the canonical history are never replaced")
+            }
         }
     }
 
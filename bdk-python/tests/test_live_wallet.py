@@ -7,6 +7,7 @@ from bdkpython import Address
 from bdkpython import Psbt
 from bdkpython import TxBuilder
 from bdkpython import Connection
+from bdkpython import ChainPosition
 from bdkpython.bitcoin import Network
 from bdkpython.bitcoin import Amount
 from bdkpython.bitcoin import FeeRate
@@ -62,6 +63,11 @@ class LiveWalletTest(unittest.TestCase):
             print(f"Transaction: {tx.transaction.compute_txid()}")
             print(f"Sent {sent_and_received.sent.to_sat()}")
             print(f"Received {sent_and_received.received.to_sat()}")
+            if isinstance(tx.chain_position, ChainPosition.UNCONFIRMED):
+                self.assertIsNone(
+                    tx.chain_position.replaced_by,
+                    "Transactions in the canonical history are never replaced"
+                )
 
 
     def test_broadcast_transaction(self):
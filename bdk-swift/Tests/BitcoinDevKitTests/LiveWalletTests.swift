@@ -65,6 +65,9 @@ final class LiveWalletTests: XCTestCase {
             print("Transaction: \(tx.transaction.computeTxid())")
             print("Sent \(sentAndReceived.sent.toSat())")
             print("Received \(sentAndReceived.received.toSat())")
+            if case let .unconfirmed(_, replacedBy) = tx.chainPosition {
+                XCTAssertNil(replacedBy, "Transactions in the canonical history are never replaced")
+            }
         }
     }
     